@@ -0,0 +1,273 @@
+use crate::output::{resolve, sibling};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+// a TOML file found in the inputs, with the directory that its place in the
+// output directory is relative to
+#[derive(Debug, PartialEq, Clone)]
+pub struct Source {
+    pub path: PathBuf,
+    pub base: PathBuf,
+}
+
+impl Source {
+    // the YAML file in the output directory, which mirrors the input tree
+    pub fn mirror(&self, out_dir: &Path) -> PathBuf {
+        let relative = self.path.strip_prefix(&self.base).unwrap_or(&self.path);
+        sibling(&out_dir.join(relative))
+    }
+}
+
+const GLOB_CHARS: [char; 3] = ['*', '?', '['];
+
+// a path that doesn't exist but has glob characters, so that files with
+// brackets in their names still work
+pub fn is_pattern(path: &Path) -> bool {
+    !path.exists() && path.to_string_lossy().contains(GLOB_CHARS)
+}
+
+// finds the files of the inputs, which are either files, directories searched
+// for `.toml` files recursively, or glob patterns. Files found twice are kept
+// once, at their first place.
+pub fn expand(paths: &[PathBuf]) -> Result<Vec<Source>, String> {
+    let mut sources = Vec::new();
+    for path in paths {
+        if is_pattern(path) {
+            expand_pattern(path, &mut sources)?;
+        } else if path.is_dir() {
+            walk(path, path, &mut sources)?;
+        } else {
+            // a file given by itself goes to the top of the output directory
+            let base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            let path = path.clone();
+            sources.push(Source { path, base });
+        }
+    }
+
+    let mut seen = HashSet::new();
+    sources.retain(|source| seen.insert(source.path.clone()));
+    Ok(sources)
+}
+
+fn expand_pattern(pattern: &Path, sources: &mut Vec<Source>) -> Result<(), String> {
+    let display = pattern.display();
+    // the directories before the first component with a glob character
+    let base: PathBuf = pattern
+        .components()
+        .take_while(|c| !c.as_os_str().to_string_lossy().contains(GLOB_CHARS))
+        .collect();
+
+    let paths = glob::glob(&pattern.to_string_lossy())
+        .map_err(|err| format!("invalid pattern `{display}`: {err}"))?;
+    let len = sources.len();
+    for path in paths {
+        let path = path.map_err(|err| format!("can't read `{display}`: {err}"))?;
+        if path.is_dir() {
+            walk(&path, &base, sources)?;
+        } else {
+            let base = base.clone();
+            sources.push(Source { path, base });
+        }
+    }
+
+    if sources.len() == len {
+        return Err(format!("no files match `{display}`"));
+    }
+    Ok(())
+}
+
+// adds the `.toml` files under the directory in the order of their names,
+// skipping hidden ones like `.git`
+fn walk(dir: &Path, base: &Path, sources: &mut Vec<Source>) -> Result<(), String> {
+    let error = |err| format!("can't read `{}`: {err}", dir.display());
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|e| e.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(error)?;
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, base, sources)?;
+        } else if path.extension().is_some_and(|ext| ext == "toml") {
+            let base = base.to_path_buf();
+            sources.push(Source { path, base });
+        }
+    }
+    Ok(())
+}
+
+// finds the inputs whose output is one of the inputs or the output of other
+// inputs, which can't be converted since they would overwrite each other.
+// Paths are compared by the files they lead to. Returns the error of each of
+// those inputs.
+pub fn conflicts(outputs: &[(&Path, PathBuf)]) -> HashMap<PathBuf, String> {
+    let sources: HashSet<_> = outputs.iter().map(|(input, _)| resolve(input)).collect();
+    let resolved: Vec<_> = outputs.iter().map(|(_, output)| resolve(output)).collect();
+    let mut inputs: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for ((input, _), output) in outputs.iter().zip(&resolved) {
+        inputs.entry(output).or_default().push(input);
+    }
+
+    let mut conflicts = HashMap::new();
+    for ((input, output), resolved) in outputs.iter().zip(&resolved) {
+        if sources.contains(resolved) {
+            let message = format!(
+                "`{}` would be written to `{}`, which is an input",
+                input.display(),
+                output.display()
+            );
+            conflicts.insert(input.to_path_buf(), message);
+            continue;
+        }
+        let others: Vec<_> = inputs[resolved.as_path()]
+            .iter()
+            .filter(|other| *other != input)
+            .map(|other| format!("`{}`", other.display()))
+            .collect();
+        if !others.is_empty() {
+            let message = format!(
+                "`{}` would be written to `{}` along with {}",
+                input.display(),
+                output.display(),
+                others.join(", ")
+            );
+            conflicts.insert(input.to_path_buf(), message);
+        }
+    }
+    conflicts
+}
+
+// calls the function on every item across as many threads as there are cores,
+// returning the results in the order of the items
+pub fn parallel<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let threads = threads.min(items.len());
+    let (next, f) = (&AtomicUsize::new(0), &f);
+
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut results = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break results;
+                        };
+                        results.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::TempDir;
+    use insta::assert_compact_debug_snapshot;
+
+    #[test]
+    fn test_expand() {
+        let dir = TempDir::new("batch");
+        for file in [
+            "a.toml",
+            "b/c.toml",
+            "b/d/e.toml",
+            "b/f.txt",
+            "b/.git/g.toml",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let paths = [dir.join("b"), dir.join("*.toml"), dir.join("b/c.toml")];
+        let out_dir = Path::new("out");
+        let r = expand(&paths).map(|sources| {
+            sources
+                .iter()
+                .map(|s| s.mirror(out_dir))
+                .collect::<Vec<_>>()
+        });
+        let none = expand(&[dir.join("*.json")]).map_err(|e| e.replace(dir.to_str().unwrap(), ""));
+        assert_compact_debug_snapshot!((r, none), @r#"(Ok(["out/c.yaml", "out/d/e.yaml", "out/a.yaml"]), Err("no files match `/*.json`"))"#)
+    }
+
+    #[test]
+    fn test_mirror() {
+        let source = |path: &str, base: &str| Source {
+            path: PathBuf::from(path),
+            base: PathBuf::from(base),
+        };
+        let r = [
+            source("config/a/b.toml", "config"),
+            source("config/b.toml", "config"),
+            source("b.toml", ""),
+        ]
+        .map(|s| s.mirror(Path::new("out")));
+        assert_compact_debug_snapshot!(r, @r#"["out/a/b.yaml", "out/b.yaml", "out/b.yaml"]"#)
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let outputs = [
+            (Path::new("a/x.toml"), PathBuf::from("out/x.yaml")),
+            (Path::new("a/y.toml"), PathBuf::from("out/y.yaml")),
+            (Path::new("b/x.toml"), PathBuf::from("out/x.yaml")),
+        ];
+        let mut r: Vec<_> = conflicts(&outputs).into_iter().collect();
+        r.sort();
+        assert_compact_debug_snapshot!(r, @r#"[("a/x.toml", "`a/x.toml` would be written to `out/x.yaml` along with `b/x.toml`"), ("b/x.toml", "`b/x.toml` would be written to `out/x.yaml` along with `a/x.toml`")]"#)
+    }
+
+    #[test]
+    fn test_conflicts_resolved() {
+        let dir = TempDir::new("conflicts");
+        fs::create_dir(dir.join("out")).unwrap();
+        let [conf, x, y, z] = ["conf.yaml", "x.toml", "y.toml", "z.toml"].map(|name| {
+            let path = dir.join(name);
+            fs::write(&path, "").unwrap();
+            path
+        });
+        let outputs = [
+            (conf.as_path(), dir.join("conf.yaml")),
+            (x.as_path(), dir.join("out/../y.toml")),
+            (y.as_path(), dir.join("a.yaml")),
+            (z.as_path(), dir.join(".").join("a.yaml")),
+        ];
+        let mut r: Vec<_> = conflicts(&outputs)
+            .into_values()
+            .map(|message| message.replace(dir.to_str().unwrap(), ""))
+            .collect();
+        r.sort();
+        assert_compact_debug_snapshot!(r, @r#"["`/conf.yaml` would be written to `/conf.yaml`, which is an input", "`/x.toml` would be written to `/out/../y.toml`, which is an input", "`/y.toml` would be written to `/a.yaml` along with `/z.toml`", "`/z.toml` would be written to `/./a.yaml` along with `/y.toml`"]"#)
+    }
+
+    #[test]
+    fn test_parallel() {
+        let items: Vec<u64> = (0..100).collect();
+        let r = parallel(&items, |i| i * 2);
+        assert_eq!(r, items.iter().map(|i| i * 2).collect::<Vec<_>>());
+    }
+}
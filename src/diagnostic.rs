@@ -0,0 +1,287 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+// ANSI escape sequences for the colors of rustc
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+// The alternate flag, as in `format!("{diagnostic:#}")`, colors the output
+
+// tabs are shown as this many spaces, like rustc does, so that the marks
+// line up with the snippet whatever the tab width of the terminal
+const TAB_WIDTH: usize = 4;
+
+fn display_width(s: impl Iterator<Item = char>) -> usize {
+    s.map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    // the document isn't valid TOML syntax
+    Syntax,
+    // the syntax is fine but a key or a table is defined twice
+    Semantic,
+}
+
+// an error located in the source, rendered like the ones of rustc
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub kind: Kind,
+    // the file of the source, which is unknown for the standard input
+    pub path: Option<PathBuf>,
+    pub message: String,
+    // both start at 1, the column counts characters
+    pub line: usize,
+    pub column: usize,
+    // byte offsets into the source
+    pub span: Range<usize>,
+    // the source line the span starts at
+    pub snippet: String,
+    // the characters of the span on that line
+    width: usize,
+    // another location related to the error, like a key's first definition
+    pub note: Option<Box<Diagnostic>>,
+}
+
+impl Diagnostic {
+    pub fn new(source: &str, span: Range<usize>, kind: Kind, message: impl Into<String>) -> Self {
+        let before = &source[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        let snippet = source[line_start..].lines().next().unwrap_or_default();
+        // an empty span like the end of input still gets a mark
+        let spanned = source[span.clone()].lines().next().unwrap_or_default();
+        let width = spanned.chars().count().max(1);
+
+        Self {
+            kind,
+            path: None,
+            message: message.into(),
+            line,
+            column,
+            span,
+            snippet: snippet.to_string(),
+            width,
+            note: None,
+        }
+    }
+
+    pub fn with_note(self, note: Diagnostic) -> Self {
+        let note = Some(Box::new(note));
+        Self { note, ..self }
+    }
+
+    pub fn with_path(self, path: &Path) -> Self {
+        let note = self.note.map(|note| Box::new(note.with_path(path)));
+        let path = Some(path.to_path_buf());
+        Self { path, note, ..self }
+    }
+
+    // writes the location and the snippet, underlining the first line of the span
+    fn fmt_snippet(&self, f: &mut Formatter<'_>, underline: char, color: &str) -> FmtResult {
+        let Self {
+            line,
+            column,
+            width,
+            ..
+        } = self;
+        let gutter = " ".repeat(line.to_string().len());
+        let (blue, color, reset) = if f.alternate() {
+            (BLUE, color, RESET)
+        } else {
+            ("", "", "")
+        };
+
+        write!(f, "{gutter}{blue}-->{reset} ")?;
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path.display())?;
+        }
+        writeln!(f, "{line}:{column}")?;
+        writeln!(f, "{gutter} {blue}|{reset}")?;
+        let snippet = self.snippet.replace('\t', &" ".repeat(TAB_WIDTH));
+        writeln!(f, "{blue}{line} |{reset} {snippet}")?;
+        let mut chars = self.snippet.chars();
+        let padding = " ".repeat(display_width(chars.by_ref().take(column - 1)));
+        let tabs = chars.take(*width).filter(|&c| c == '\t').count();
+        let marks = underline.to_string().repeat(width + tabs * (TAB_WIDTH - 1));
+        write!(f, "{gutter} {blue}|{reset} {padding}{color}{marks}{reset}")
+    }
+}
+
+// the first line of a message like `error: ...`
+pub struct Title<'a> {
+    pub level: &'a str,
+    pub message: &'a str,
+}
+
+impl Display for Title<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let Self { level, message } = self;
+        if f.alternate() {
+            let color = match *level {
+                "error" | "failed" => RED,
+                _ => GREEN,
+            };
+            write!(f, "{color}{level}{RESET}{BOLD}: {message}{RESET}")
+        } else {
+            write!(f, "{level}: {message}")
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let title = Title {
+            level: "error",
+            message: &self.message,
+        };
+        title.fmt(f)?;
+        f.write_char('\n')?;
+        self.fmt_snippet(f, '^', RED)?;
+
+        if let Some(note) = &self.note {
+            let title = Title {
+                level: "note",
+                message: &note.message,
+            };
+            f.write_str("\n\n")?;
+            title.fmt(f)?;
+            f.write_char('\n')?;
+            note.fmt_snippet(f, '-', BLUE)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse;
+    use insta::{assert_compact_debug_snapshot, assert_snapshot};
+    use std::path::Path;
+
+    #[test]
+    fn test_diagnostic_syntax() {
+        let r = [
+            "a = 1\nb = ",
+            "a = 1 b",
+            "[a\nb = 1",
+            "s = \"abc\nt = 1",
+            "= 1",
+        ]
+        .map(|s| parse(s).unwrap_err().remove(0).to_string())
+        .join("\n\n");
+        assert_snapshot!(r, @r#"
+        error: expected a value
+         --> 2:5
+          |
+        2 | b = 
+          |     ^
+
+        error: expected a new line
+         --> 1:7
+          |
+        1 | a = 1 b
+          |       ^
+
+        error: expected `]`
+         --> 1:3
+          |
+        1 | [a
+          |   ^
+
+        error: expected a closing `"`
+         --> 1:9
+          |
+        1 | s = "abc
+          |         ^
+
+        error: expected a key or a table header
+         --> 1:1
+          |
+        1 | = 1
+          | ^
+        "#)
+    }
+
+    #[test]
+    fn test_diagnostic_tabs() {
+        let r = ["k =\t\"v\"\t\tx", "\tk = 1\n\tk\t= 2"]
+            .map(|s| parse(s).unwrap_err().remove(0).to_string())
+            .join("\n\n");
+        assert_snapshot!(r, @r#"
+        error: expected a new line
+         --> 1:10
+          |
+        1 | k =    "v"        x
+          |                   ^
+
+        error: duplicate key
+         --> 2:2
+          |
+        2 |     k    = 2
+          |     ^^^^^^^^
+
+        note: first defined here
+         --> 1:2
+          |
+        1 |     k = 1
+          |     -----
+        "#)
+    }
+
+    #[test]
+    fn test_diagnostic_semantic() {
+        let r = parse("[a]\nx = 1\n\n[b]\n\n[a]\ny = 2\n")
+            .unwrap_err()
+            .remove(0);
+        assert_snapshot!(r, @r"
+        error: duplicate table
+         --> 6:1
+          |
+        6 | [a]
+          | ^^^
+
+        note: first defined here
+         --> 1:1
+          |
+        1 | [a]
+          | ---
+        ")
+    }
+
+    #[test]
+    fn test_diagnostic_path() {
+        let r = parse("a = 1\na = 2")
+            .unwrap_err()
+            .remove(0)
+            .with_path(Path::new("config/a.toml"));
+        assert_snapshot!(r, @r"
+        error: duplicate key
+         --> config/a.toml:2:1
+          |
+        2 | a = 2
+          | ^^^^^
+
+        note: first defined here
+         --> config/a.toml:1:1
+          |
+        1 | a = 1
+          | -----
+        ")
+    }
+
+    #[test]
+    fn test_diagnostic_location() {
+        let s = "# comment\n[t]\nkey = \"héllo\" $\n";
+        let r = parse(s).unwrap_err().remove(0);
+        assert_compact_debug_snapshot!(r, @r#"Diagnostic { kind: Syntax, path: None, message: "expected a new line", line: 3, column: 15, span: 29..30, snippet: "key = \"héllo\" $", width: 1, note: None }"#)
+    }
+}
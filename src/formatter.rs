@@ -0,0 +1,213 @@
+use crate::ir::{Array, Document, Identifier, InlineTable, Value};
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+// Writes the document back as TOML. Tables become `[table]` sections and
+// arrays of tables `[[array]]` sections, everything else is written inline.
+// Comments and the original layout are lost in parsing, so they aren't kept.
+pub struct Toml<'a>(pub &'a Document);
+
+impl Display for Toml<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let mut s = String::new();
+        write_table(&mut s, &mut Vec::new(), &self.0 .0)?;
+        // sections start with an empty line, even the first one
+        f.write_str(s.trim_matches('\n'))
+    }
+}
+
+// writes the pairs of the table before its sections, since pairs after a
+// header belong to that header
+fn write_table<'a>(
+    w: &mut impl Write,
+    path: &mut Vec<&'a Identifier>,
+    table: &'a InlineTable,
+) -> FmtResult {
+    for pair in table.pairs().iter().filter(|p| !p.value.is_table()) {
+        write_key(w, &pair.key)?;
+        w.write_str(" = ")?;
+        write_value(w, &pair.value)?;
+        w.write_char('\n')?;
+    }
+
+    for pair in table.pairs().iter().filter(|p| p.value.is_table()) {
+        path.push(&pair.key);
+        match &pair.value {
+            Value::InlineTable(table) => {
+                write_header(w, path, "[", "]")?;
+                write_table(w, path, table)?;
+            }
+            Value::Array(Array(vec)) => {
+                for value in vec {
+                    let Value::InlineTable(table) = value else {
+                        unreachable!("arrays of tables only hold tables")
+                    };
+                    write_header(w, path, "[[", "]]")?;
+                    write_table(w, path, table)?;
+                }
+            }
+            _ => unreachable!(),
+        }
+        path.pop();
+    }
+
+    Ok(())
+}
+
+fn write_header(w: &mut impl Write, path: &[&Identifier], open: &str, close: &str) -> FmtResult {
+    write!(w, "\n{open}")?;
+    for (i, key) in path.iter().enumerate() {
+        if i > 0 {
+            w.write_char('.')?;
+        }
+        write_key(w, key)?;
+    }
+    writeln!(w, "{close}")
+}
+
+fn write_key(w: &mut impl Write, key: &Identifier) -> FmtResult {
+    let is_bare = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if !key.0.is_empty() && key.0.chars().all(is_bare) {
+        w.write_str(&key.0)
+    } else {
+        write_basic_string(w, &key.0)
+    }
+}
+
+fn write_value(w: &mut impl Write, value: &Value) -> FmtResult {
+    match value {
+        Value::Integer(v) => write!(w, "{v}"),
+        Value::Float(v) if v.is_nan() => w.write_str("nan"),
+        Value::Float(v) if v.is_infinite() && v.is_sign_positive() => w.write_str("inf"),
+        Value::Float(v) if v.is_infinite() => w.write_str("-inf"),
+        // keeps the fraction of whole numbers like `1.0`
+        Value::Float(v) => write!(w, "{v:?}"),
+        Value::Boolean(v) => write!(w, "{v}"),
+        Value::String(v) => write_basic_string(w, v),
+        Value::OffsetDateTime(d, t, o) => write!(w, "{d}T{t}{o}"),
+        Value::LocalDateTime(d, t) => write!(w, "{d}T{t}"),
+        Value::LocalDate(d) => write!(w, "{d}"),
+        Value::LocalTime(t) => write!(w, "{t}"),
+        Value::Array(Array(vec)) => {
+            w.write_char('[')?;
+            for (i, value) in vec.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_value(w, value)?;
+            }
+            w.write_char(']')
+        }
+        Value::InlineTable(table) if table.is_empty() => w.write_str("{}"),
+        Value::InlineTable(table) => {
+            w.write_str("{ ")?;
+            for (i, pair) in table.pairs().iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_key(w, &pair.key)?;
+                w.write_str(" = ")?;
+                write_value(w, &pair.value)?;
+            }
+            w.write_str(" }")
+        }
+    }
+}
+
+fn write_basic_string(w: &mut impl Write, s: &str) -> FmtResult {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\u{08}' => w.write_str("\\b")?,
+            '\t' => w.write_str("\\t")?,
+            '\n' => w.write_str("\\n")?,
+            '\u{0C}' => w.write_str("\\f")?,
+            '\r' => w.write_str("\\r")?,
+            c if c.is_control() => write!(w, "\\u{:04X}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+#[cfg(test)]
+mod test {
+    use super::Toml;
+    use crate::parser::{parse, test::TOML};
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_format_toml() {
+        let doc = parse(TOML).unwrap();
+        let r = Toml(&doc).to_string();
+
+        assert_snapshot!(r, @r#"
+        title = "TOML Example"
+
+        [owner]
+        name = "Tom Preston-Werner"
+
+        [database]
+        enabled = true
+        ports = [8000, 8001, 8002]
+        data = [["delta", "phi"], [3.14, { a = 72.0, b = 26 }]]
+
+        [database.temp_targets]
+        cpu = 79.5
+
+        [database.temp_targets.case]
+        a = 72.0
+        b = 26
+
+        [servers-alpha]
+        ip = "10.0.0.1"
+        role = "frontend"
+
+        [servers-beta]
+        ip = "10.0.0.2"
+        role = "backend"
+        "#)
+    }
+
+    #[test]
+    fn test_format_toml_round_trip() {
+        let s = r#"
+[[fruit]]
+name = "apple"
+physical.color = "red"
+"key with \"quotes\"" = 'tab	and \n'
+when = 1979-05-27 07:32:00Z
+[[fruit.variety]]
+name = "red delicious"
+[[fruit]]
+name = "banana"
+sizes = [1, 2.5, inf, { a = [] }]
+
+[empty]
+"#;
+        let doc = parse(s).unwrap();
+        let r = Toml(&doc).to_string();
+        // tables move after the pairs, so the order of keys may change once
+        assert_eq!(Toml(&parse(&r).unwrap()).to_string(), r);
+
+        assert_snapshot!(r, @r#"
+        [[fruit]]
+        name = "apple"
+        "key with \"quotes\"" = "tab\tand \\n"
+        when = 1979-05-27T07:32:00Z
+
+        [fruit.physical]
+        color = "red"
+
+        [[fruit.variety]]
+        name = "red delicious"
+
+        [[fruit]]
+        name = "banana"
+        sizes = [1, 2.5, inf, { a = [] }]
+
+        [empty]
+        "#)
+    }
+}
@@ -1,149 +1,707 @@
-use crate::ir::{Array, Document, Identifier, InlineTable, Pair, Table, Value};
-use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+use crate::ir::{Array, Date, Document, Identifier, InlineTable, Offset, Pair, Time, Value};
+use std::{
+    fmt::{self, Display, Formatter, Result as FmtResult, Write},
+    io,
+};
 
-const INDENTATION: &str = "  ";
+// the quotes for strings that need them, the other ones are used when the
+// preferred ones can't hold the string
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QuoteStyle {
+    Single,
+    Double,
+}
 
-// It's better to use a custom trait named like DisplayYaml instead of Display
+// which arrays and tables are written in flow style, like `[a, b]` and
+// `{a: 1}`, as TOML writes inline arrays and tables
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Flow {
+    Never,
+    // the ones that fit in the width that way
+    Width(usize),
+    // all but the top-level table
+    Always,
+}
 
-impl Display for Identifier {
+#[derive(Debug, Clone)]
+pub struct YamlOptions {
+    // the spaces for each level of nesting, at least 2 to fit `- `
+    pub indent: usize,
+    // whether sequences are indented under their key or flush with it
+    pub indent_sequences: bool,
+    // the empty lines around top-level tables
+    pub blank_lines: usize,
+    // starts the document with `---`
+    pub document_start: bool,
+    pub quote_style: QuoteStyle,
+    // the width strings are folded to at their spaces, if any
+    pub line_width: Option<usize>,
+    // quotes dates and times instead of writing YAML timestamps
+    pub strict: bool,
+    pub flow: Flow,
+}
+
+impl Default for YamlOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            indent_sequences: true,
+            blank_lines: 1,
+            document_start: false,
+            quote_style: QuoteStyle::Single,
+            line_width: None,
+            strict: false,
+            flow: Flow::Never,
+        }
+    }
+}
+
+// Writes YAML straight to the output, keeping track of the column it's at and
+// of the column new lines start at, so that nested values are written where
+// they belong in one pass
+pub struct Emitter<'a> {
+    out: &'a mut dyn Write,
+    options: &'a YamlOptions,
+    // the column of the line a value belongs to, like the one of its key,
+    // which its lines after the first one are written relative to
+    indent: usize,
+    column: usize,
+    // the indentation of a new line is written along with its first
+    // characters, so that empty lines have no trailing spaces
+    pending: bool,
+    // whether a flow collection is being written, which everything in it
+    // belongs to as well
+    flow: bool,
+    // whether the last thing written is a string that keeps its line breaks,
+    // which empty lines after it would become part of
+    kept: bool,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(out: &'a mut dyn Write, options: &'a YamlOptions) -> Self {
+        Self {
+            out,
+            options,
+            indent: 0,
+            column: 0,
+            pending: false,
+            flow: false,
+            kept: false,
+        }
+    }
+
+    fn newline(&mut self) -> FmtResult {
+        self.out.write_char('\n')?;
+        self.column = 0;
+        self.pending = true;
+        Ok(())
+    }
+
+    // writes the lines of `f` after the first one at the column
+    fn indented(&mut self, indent: usize, f: impl FnOnce(&mut Self) -> FmtResult) -> FmtResult {
+        let outer = std::mem::replace(&mut self.indent, indent);
+        let result = f(self);
+        self.indent = outer;
+        result
+    }
+
+    // writes a block starting at the current column, with its lines relative
+    // to that column
+    fn block(&mut self, f: impl FnOnce(&mut Self) -> FmtResult) -> FmtResult {
+        let column = if self.pending {
+            self.indent
+        } else {
+            self.column
+        };
+        self.indented(column, f)
+    }
+
+    fn in_flow(&mut self, f: impl FnOnce(&mut Self) -> FmtResult) -> FmtResult {
+        let outer = std::mem::replace(&mut self.flow, true);
+        let result = f(self);
+        self.flow = outer;
+        result
+    }
+
+    // whether the array or table is written in flow style
+    fn is_flow(&self, v: &Value) -> bool {
+        self.flow
+            || match self.options.flow {
+                Flow::Never => false,
+                Flow::Width(width) => fits(v, width, self.options),
+                Flow::Always => true,
+            }
+    }
+}
+
+// counts the characters written, failing once there are more than the limit
+struct Counter {
+    len: usize,
+    limit: usize,
+}
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.len += s.chars().count();
+        if self.len > self.limit {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+// whether the value in flow style is at most the width, which stops writing
+// it as soon as it's wider
+fn fits(v: &Value, width: usize, options: &YamlOptions) -> bool {
+    let mut counter = Counter {
+        len: 0,
+        limit: width,
+    };
+    let mut e = Emitter::new(&mut counter, options);
+    e.flow = true;
+    v.fmt_yaml(&mut e).is_ok()
+}
+
+// text written with `write!` stays on the current line
+impl Write for Emitter<'_> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        if self.pending {
+            write!(self.out, "{:1$}", "", self.indent)?;
+            self.column = self.indent;
+            self.pending = false;
+        }
+        self.out.write_str(s)?;
+        self.column += s.chars().count();
+        self.kept = false;
+        Ok(())
+    }
+}
+
+// Like Display, but for YAML, which depends on the options and on where the
+// value is written
+pub trait DisplayYaml {
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult;
+}
+
+// writes the value as a YAML document, as in `Yaml(&doc, &options).to_string()`
+pub struct Yaml<'a, T>(pub &'a T, pub &'a YamlOptions);
+
+impl<T: DisplayYaml> Display for Yaml<'_, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        self.0.fmt_yaml(&mut Emitter::new(f, self.1))
+    }
+}
+
+// passes what's written on to an io::Write, keeping its error since
+// fmt::Error can't hold one
+struct IoWriter<W> {
+    inner: W,
+    error: io::Result<()>,
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Err(err);
+            fmt::Error
+        })
+    }
+}
+
+// writes the value as a YAML document to an io::Write, without building it
+// in memory first
+pub fn write_yaml(
+    w: impl io::Write,
+    v: &impl DisplayYaml,
+    options: &YamlOptions,
+) -> io::Result<()> {
+    let mut out = IoWriter {
+        inner: w,
+        error: Ok(()),
+    };
+    match v.fmt_yaml(&mut Emitter::new(&mut out, options)) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => out.error.and(Err(io::Error::other("formatter error"))),
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let Self { year, month, day } = self;
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl Display for Time {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        self.0.fmt(f)
+        let Self {
+            hour,
+            minute,
+            second,
+            nanosecond,
+        } = self;
+        write!(f, "{hour:02}:{minute:02}:{second:02}")?;
+        if *nanosecond != 0 {
+            let fraction = format!("{nanosecond:09}");
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+
+        Ok(())
     }
 }
 
-impl Display for Value {
+impl Display for Offset {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         match self {
-            Self::Integer(v) => v.fmt(f),
-            // See: https://doc.rust-lang.org/std/fmt/index.html
-            Self::Float(v) => write!(f, "{:?}", v),
-            Self::Boolean(v) => v.fmt(f),
-            Self::String(v) => v.fmt(f),
-            Self::Array(v) => indent_inbetween(f, &v.to_string()),
-            Self::InlineTable(v) => indent_inbetween(f, &v.to_string()),
+            Self::Z => f.write_char('Z'),
+            Self::Minutes(m) => {
+                let sign = if *m < 0 { '-' } else { '+' };
+                write!(f, "{sign}{:02}:{:02}", m.abs() / 60, m.abs() % 60)
+            }
+        }
+    }
+}
+
+impl DisplayYaml for Identifier {
+    // keys are strings as well, but with no `:` at all, since some parsers
+    // read `a:b: 1` as the key `a`
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
+        let s = &self.0;
+        let quote = e.options.quote_style;
+        match Style::of(s, quote, e.flow) {
+            Style::Plain if s.contains(':') => write_styled(e, s, Style::quoted(s, quote)),
+            style => write_styled(e, s, style),
+        }
+    }
+}
+
+impl DisplayYaml for Value {
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
+        let strict = e.options.strict;
+        match self {
+            Self::Integer(v) => write!(e, "{v}"),
+            Self::Float(v) if v.is_nan() => e.write_str(".nan"),
+            Self::Float(v) if v.is_infinite() && v.is_sign_positive() => e.write_str(".inf"),
+            Self::Float(v) if v.is_infinite() => e.write_str("-.inf"),
+            Self::Float(v) => write_float(e, *v),
+            Self::Boolean(v) => write!(e, "{v}"),
+            // flow collections have no block scalars
+            Self::String(v) => match block_header(v) {
+                Some(header) if !e.flow => write_literal(e, v, header),
+                _ => write_string(e, v),
+            },
+            Self::OffsetDateTime(d, t, o) if strict => write!(e, "\"{d}T{t}{o}\""),
+            Self::OffsetDateTime(d, t, o) => write!(e, "{d}T{t}{o}"),
+            Self::LocalDateTime(d, t) if strict => write!(e, "\"{d}T{t}\""),
+            Self::LocalDateTime(d, t) => write!(e, "{d}T{t}"),
+            Self::LocalDate(d) if strict => write!(e, "\"{d}\""),
+            Self::LocalDate(d) => write!(e, "{d}"),
+            // YAML has no type for a time of day and YAML 1.1 reads `07:32:00`
+            // as a base 60 integer, so it's quoted in both modes
+            Self::LocalTime(t) => write!(e, "\"{t}\""),
+            // nothing at all would be a null
+            Self::Array(v) if v.0.is_empty() => e.write_str("[]"),
+            Self::InlineTable(v) if v.is_empty() => e.write_str("{}"),
+            Self::Array(v) if e.is_flow(self) => e.in_flow(|e| v.fmt_yaml(e)),
+            Self::InlineTable(v) if e.is_flow(self) => e.in_flow(|e| v.fmt_yaml(e)),
+            Self::Array(v) => v.fmt_yaml(e),
+            Self::InlineTable(v) => v.fmt_yaml(e),
         }
     }
 }
 
-impl Display for Array {
+impl DisplayYaml for Array {
     // puts hyphen before each array item and
     // puts newline between array items
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let mut iter = self.0.iter();
-        if let Some(value) = iter.next() {
-            f.write_str("- ")?;
-            value.fmt(f)?;
-        }
-        for value in iter {
-            f.write_char('\n')?;
-            f.write_str("- ")?;
-            value.fmt(f)?;
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
+        if e.flow {
+            return write_flow(e, &self.0, '[', ']');
         }
 
-        Ok(())
+        // the hyphen takes the room of a level of nesting
+        let hyphen = format!("{:<1$}", "-", e.options.indent);
+        e.block(|e| {
+            for (i, value) in self.0.iter().enumerate() {
+                if i > 0 {
+                    e.newline()?;
+                }
+                e.write_str(&hyphen)?;
+                value.fmt_yaml(e)?;
+            }
+            Ok(())
+        })
     }
 }
 
-impl Display for InlineTable {
+impl DisplayYaml for InlineTable {
     // puts newline between table pairs
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let mut iter = self.0.iter();
-        if let Some(pair) = iter.next() {
-            pair.fmt(f)?;
-        }
-        for pair in iter {
-            f.write_char('\n')?;
-            pair.fmt(f)?;
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
+        if e.flow {
+            return write_flow(e, self.pairs(), '{', '}');
         }
 
-        Ok(())
+        e.block(|e| {
+            for (i, pair) in self.pairs().iter().enumerate() {
+                if i > 0 {
+                    e.newline()?;
+                }
+                pair.fmt_yaml(e)?;
+            }
+            Ok(())
+        })
     }
 }
 
-impl Display for Pair {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+impl DisplayYaml for Pair {
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
         let Self { key, value } = self;
-
-        key.fmt(f)?;
-        f.write_char(':')?;
+        key.fmt_yaml(e)?;
+        e.write_char(':')?;
 
         use Value::*;
         match value {
-            Integer(_) | Float(_) | Boolean(_) | String(_) => {
-                f.write_char(' ')?;
+            Integer(_) | Float(_) | Boolean(_) | String(_) | OffsetDateTime(..)
+            | LocalDateTime(..) | LocalDate(_) | LocalTime(_) => {
+                e.write_char(' ')?;
+                value.fmt_yaml(e)
+            }
+            InlineTable(v) if v.is_empty() => {
+                e.write_char(' ')?;
+                value.fmt_yaml(e)
+            }
+            Array(v) if v.0.is_empty() => {
+                e.write_char(' ')?;
+                value.fmt_yaml(e)
+            }
+            Array(_) | InlineTable(_) if e.is_flow(value) => {
+                e.write_char(' ')?;
+                value.fmt_yaml(e)
             }
-            InlineTable(_) | Array(_) => {
-                f.write_char('\n')?;
-                f.write_str(INDENTATION)?;
+            Array(_) if !e.options.indent_sequences => {
+                e.newline()?;
+                value.fmt_yaml(e)
             }
+            InlineTable(_) | Array(_) => e.indented(e.indent + e.options.indent, |e| {
+                e.newline()?;
+                value.fmt_yaml(e)
+            }),
         }
-
-        value.fmt(f)
     }
 }
 
-impl Display for Table {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let Self { header, body } = self;
+impl DisplayYaml for Document {
+    // puts empty lines around top-level tables
+    fn fmt_yaml(&self, e: &mut Emitter<'_>) -> FmtResult {
+        if e.options.document_start {
+            e.write_str("---")?;
+            e.newline()?;
+        }
 
-        if header.0.is_empty() {
-            body.fmt(f)
-        } else {
-            header.fmt(f)?;
-            f.write_str(":\n")?;
-            indent_all(f, &body.to_string())
+        let pairs = self.0.pairs();
+        // nothing at all would be read as null
+        if pairs.is_empty() {
+            return e.write_str("{}");
         }
+        for (i, pair) in pairs.iter().enumerate() {
+            if i > 0 {
+                let prev = &pairs[i - 1];
+                // tables in flow style take a line like any other value
+                let is_section = |v: &Value| v.is_table() && !e.is_flow(v);
+                let is_around = is_section(&prev.value) || is_section(&pair.value);
+                e.newline()?;
+                // the empty lines would become part of a string that keeps
+                // its line breaks, even one in a table
+                if is_around && !e.kept {
+                    for _ in 0..e.options.blank_lines {
+                        e.newline()?;
+                    }
+                }
+            }
+            pair.fmt_yaml(e)?;
+        }
+
+        Ok(())
     }
 }
 
-impl Display for Document {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let mut iter = self.0.iter();
-        if let Some(table) = iter.next() {
-            table.fmt(f)?;
+// how a string has to be written for YAML to read it back unchanged
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Style {
+    Plain,
+    SingleQuoted,
+    // the only style with escapes, needed for control characters
+    DoubleQuoted,
+}
+
+impl Style {
+    fn of(s: &str, quote: QuoteStyle, flow: bool) -> Self {
+        if is_plain(s) && !(flow && s.contains(FLOW_INDICATORS)) {
+            Self::Plain
+        } else {
+            Self::quoted(s, quote)
         }
-        for table in iter {
-            f.write_str("\n\n")?;
-            table.fmt(f)?;
+    }
+
+    fn quoted(s: &str, quote: QuoteStyle) -> Self {
+        if quote == QuoteStyle::Double || s.chars().any(|c| c == '\'' || needs_escape(c)) {
+            Self::DoubleQuoted
+        } else {
+            Self::SingleQuoted
         }
+    }
+}
 
-        Ok(())
+// characters that mean something to YAML at the start of a scalar
+const INDICATORS: &str = "-?:[]{},#&*!|>'\"%@`";
+
+// characters that end a plain scalar in a flow collection, where `:` can
+// start a value without a space after it and YAML 1.1 reads `?` as a key
+const FLOW_INDICATORS: [char; 7] = [',', '[', ']', '{', '}', ':', '?'];
+
+// characters that can't appear literally in a scalar, along with the ones
+// YAML 1.1 reads as line breaks
+fn needs_escape(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{2028}' | '\u{2029}' | '\u{FEFF}' | '\u{FFFE}' | '\u{FFFF}'
+        )
+}
+
+// whether YAML 1.1 and 1.2 both read the string as itself without quotes,
+// that is it doesn't look like another type and has no characters that mean
+// something to YAML where they are
+fn is_plain(s: &str) -> bool {
+    let mut chars = s.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    let is_indicator = match first {
+        // `-` starts a sequence item only when followed by a space
+        '-' => chars.next().is_none_or(|c| c == ' '),
+        c => INDICATORS.contains(c),
+    };
+
+    !is_indicator
+        && !s.starts_with(' ')
+        && !s.ends_with(' ')
+        // document markers
+        && !s.starts_with("---")
+        && !s.starts_with("...")
+        // a key or a comment
+        && !s.contains(": ")
+        && !s.ends_with(':')
+        && !s.contains(" #")
+        && !s.chars().any(needs_escape)
+        && !is_reserved(s)
+}
+
+// whether a plain scalar would be read as null, a boolean, a number or a
+// timestamp, by YAML 1.1 or 1.2
+fn is_reserved(s: &str) -> bool {
+    #[rustfmt::skip]
+    const WORDS: [&str; 28] = [
+        "~", "null", "Null", "NULL",
+        "y", "Y", "yes", "Yes", "YES", "n", "N", "no", "No", "NO",
+        "true", "True", "TRUE", "false", "False", "FALSE",
+        "on", "On", "ON", "off", "Off", "OFF",
+        // the merge key and the value key of YAML 1.1
+        "<<", "=",
+    ];
+    // dates like `2001-12-14`, times come after them
+    let b = s.as_bytes();
+    let is_timestamp = b.len() >= 8 && b[..4].iter().all(u8::is_ascii_digit) && b[4] == b'-';
+    WORDS.contains(&s) || is_timestamp || is_number(s)
+}
+
+// matches integers and floats of any base and format, including the base 60
+// ones of YAML 1.1 like `1:30`, erring on the side of too many matches
+fn is_number(s: &str) -> bool {
+    let digits = |s: &str, radix| !s.is_empty() && s.chars().all(|c| c == '_' || c.is_digit(radix));
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(s) = s.strip_prefix(prefix) {
+            return digits(s, radix);
+        }
+    }
+    if matches!(s, ".inf" | ".Inf" | ".INF" | ".nan" | ".NaN" | ".NAN") {
+        return true;
     }
+    if s.contains(':') {
+        let is_part = |s: &str| {
+            s.chars()
+                .all(|c| c.is_ascii_digit() || c == '_' || c == '.')
+        };
+        return s.split(':').all(|s| !s.is_empty() && is_part(s));
+    }
+
+    let (mantissa, exponent) = s.split_once(['e', 'E']).unwrap_or((s, "0"));
+    let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+    let is_mantissa = mantissa.matches('.').count() <= 1
+        && mantissa.chars().any(|c| c.is_ascii_digit())
+        && mantissa
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '_' || c == '.');
+    is_mantissa && digits(exponent, 10)
 }
 
-// puts indentation between lines
-fn indent_inbetween(f: &mut Formatter<'_>, s: &str) -> FmtResult {
-    let mut iter = s.split_inclusive("\n");
-    if let Some(line) = iter.next() {
-        f.write_str(line)?;
+// writes a float as YAML 1.1 reads it too, which needs a `.` and a signed
+// exponent, like `1.0e+22` for the `1e22` of Debug
+fn write_float(e: &mut Emitter<'_>, v: f64) -> FmtResult {
+    // keeps the fraction of whole numbers like `1.0`
+    let s = format!("{v:?}");
+    match s.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let fraction = if mantissa.contains('.') { "" } else { ".0" };
+            let sign = if exponent.starts_with('-') { "" } else { "+" };
+            write!(e, "{mantissa}{fraction}e{sign}{exponent}")
+        }
+        None => e.write_str(&s),
     }
-    for line in iter {
-        f.write_str(INDENTATION)?;
-        f.write_str(line)?;
+}
+
+fn write_string(e: &mut Emitter<'_>, s: &str) -> FmtResult {
+    let style = Style::of(s, e.options.quote_style, e.flow);
+    // flow collections are kept on one line
+    let Some(width) = e.options.line_width.filter(|_| !e.flow) else {
+        return write_styled(e, s, style);
+    };
+    let mut styled = String::new();
+    write_styled(&mut styled, s, style)?;
+    write_folded(e, &styled, width)
+}
+
+fn write_styled(f: &mut impl Write, s: &str, style: Style) -> FmtResult {
+    match style {
+        Style::Plain => f.write_str(s),
+        Style::SingleQuoted => write_single_quoted(f, s),
+        Style::DoubleQuoted => write_double_quoted(f, s),
     }
+}
+
+// returns the header of a literal block scalar for a multi-line string, if
+// one can hold it. The chomping indicator keeps as many line breaks at the
+// end as the string has.
+fn block_header(s: &str) -> Option<&'static str> {
+    // the indentation of the content is taken from its first line that isn't
+    // empty, so that line can't start with spaces of its own
+    let first = s
+        .split('\n')
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    let is_block = s.contains('\n')
+        && !first.is_empty()
+        && !first.starts_with([' ', '\t'])
+        && !s.chars().any(|c| c != '\n' && c != '\t' && needs_escape(c));
+
+    let header = match s.len() - s.trim_end_matches('\n').len() {
+        0 => "|-",
+        1 => "|",
+        _ => "|+",
+    };
+    is_block.then_some(header)
+}
 
-    Ok(())
+// writes a literal block scalar indented under its key or hyphen. The line
+// break of the last line is written by whatever comes next.
+fn write_literal(e: &mut Emitter<'_>, s: &str, header: &str) -> FmtResult {
+    e.write_str(header)?;
+    e.indented(e.indent + e.options.indent, |e| {
+        for line in s.strip_suffix('\n').unwrap_or(s).split('\n') {
+            e.newline()?;
+            if !line.is_empty() {
+                e.write_str(line)?;
+            }
+        }
+        e.kept = header == "|+";
+        Ok(())
+    })
 }
 
-// puts indentation before each line
-fn indent_all(f: &mut Formatter<'_>, s: &str) -> FmtResult {
-    for line in s.split_inclusive("\n") {
-        f.write_str(INDENTATION)?;
-        f.write_str(line)?;
+// writes a quoted or plain scalar over several lines when it's longer than
+// the width, by breaking it at spaces. YAML reads each of these line breaks
+// as a space, so only single spaces between other characters are used.
+fn write_folded(e: &mut Emitter<'_>, s: &str, width: usize) -> FmtResult {
+    let is_break = |i: usize| {
+        let prev = s[..i].chars().next_back();
+        let next = s[i + 1..].chars().next();
+        // a line starting with an indicator might not read as a continuation
+        prev.is_some_and(|c| c != ' ') && next.is_some_and(|c| c != ' ' && !INDICATORS.contains(c))
+    };
+    let breaks: Vec<_> = s
+        .match_indices(' ')
+        .map(|(i, _)| i)
+        .filter(|i| is_break(*i))
+        .collect();
+
+    e.indented(e.indent + e.options.indent, |e| {
+        let mut start = 0;
+        for end in breaks.into_iter().chain([s.len()]) {
+            let word = &s[start..end];
+            if start > 0 && e.column + 1 + word.chars().count() > width {
+                e.newline()?;
+            } else if start > 0 {
+                e.write_char(' ')?;
+            }
+            e.write_str(word)?;
+            start = end + 1;
+        }
+        Ok(())
+    })
+}
+
+// writes the items of a flow collection between the brackets
+fn write_flow(
+    e: &mut Emitter<'_>,
+    items: &[impl DisplayYaml],
+    open: char,
+    close: char,
+) -> FmtResult {
+    e.write_char(open)?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            e.write_str(", ")?;
+        }
+        item.fmt_yaml(e)?;
     }
+    e.write_char(close)
+}
 
-    Ok(())
+// writes a YAML single-quoted scalar, where only quotes are escaped, by
+// doubling them
+fn write_single_quoted(f: &mut impl Write, s: &str) -> FmtResult {
+    write!(f, "'{}'", s.replace('\'', "''"))
+}
+
+// writes a YAML double-quoted scalar, escaping what can't appear literally
+fn write_double_quoted(f: &mut impl Write, s: &str) -> FmtResult {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\0' => f.write_str("\\0")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            c if needs_escape(c) => write!(f, "\\u{:04X}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
 }
 
 #[cfg(test)]
 mod test {
+    use super::{write_yaml, Flow, QuoteStyle, Yaml, YamlOptions};
     use crate::parser::{parse, test::TOML};
     use insta::assert_snapshot;
 
     #[test]
     fn test_display_yaml() {
         let doc = parse(TOML).unwrap();
-        let r = doc.to_string();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
 
         assert_snapshot!(r, @r"
         title: TOML Example
@@ -178,4 +736,545 @@ mod test {
           role: backend
         ")
     }
+
+    #[test]
+    fn test_display_yaml_options() {
+        let s = r#"
+title = "a title that is long enough to be folded over lines, twice or more"
+on = ["it's", "- item"]
+
+[database]
+ports = [8000, 8001]
+data = [["delta", "phi"], [3.14, { a = 72.0, b = 26 }]]
+text = "one\ntwo\n"
+
+[[products]]
+name = "Hammer"
+"#;
+        let doc = parse(s).unwrap();
+        let options = YamlOptions {
+            indent: 4,
+            indent_sequences: false,
+            blank_lines: 0,
+            document_start: true,
+            quote_style: QuoteStyle::Double,
+            line_width: Some(30),
+            strict: false,
+            flow: Flow::Never,
+        };
+        let r = Yaml(&doc, &options).to_string();
+
+        assert_snapshot!(r, @r#"
+        ---
+        title: a title that is long
+            enough to be folded over
+            lines, twice or more
+        "on":
+        -   it's
+        -   "- item"
+        database:
+            ports:
+            -   8000
+            -   8001
+            data:
+            -   -   delta
+                -   phi
+            -   -   3.14
+                -   a: 72.0
+                    b: 26
+            text: |
+                one
+                two
+        products:
+        -   name: Hammer
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_dotted_keys() {
+        let s = "server.host = \"localhost\"\nserver.port = 80\n\n[database]\nconn.pool.size = 4\nconn.pool.idle = 2\n";
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        server:
+          host: localhost
+          port: 80
+
+        database:
+          conn:
+            pool:
+              size: 4
+              idle: 2
+        ")
+    }
+
+    #[test]
+    fn test_display_yaml_quoted_keys() {
+        let s = r#"
+"127.0.0.1" = "localhost"
+'key with spaces' = 1
+"" = 2
+"say \"hi\"".'a\b' = 3
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r#"
+        127.0.0.1: localhost
+        key with spaces: 1
+        '': 2
+
+        say "hi":
+          a\b: 3
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_reserved_keys() {
+        let s = r#"
+on = 1
+123 = 2
+"?x" = 3
+"a:b" = 4
+"" = 5
+"- a" = 6
+"null".'#' = 7
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        'on': 1
+        '123': 2
+        '?x': 3
+        'a:b': 4
+        '': 5
+        '- a': 6
+
+        'null':
+          '#': 7
+        ")
+    }
+
+    #[test]
+    fn test_display_yaml_dotted_tables() {
+        let s = r#"
+[servers.alpha]
+ip = "10.0.0.1"
+
+[ servers . "beta" ]
+ip = "10.0.0.2"
+
+[servers]
+count = 2
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        servers:
+          alpha:
+            ip: 10.0.0.1
+          beta:
+            ip: 10.0.0.2
+          count: 2
+        ")
+    }
+
+    #[test]
+    fn test_display_yaml_array_of_tables() {
+        let s = r#"
+[[products]]
+name = "Hammer"
+sku = 738594937
+
+[[products]]
+
+[[products]]
+name = "Nail"
+color = "gray"
+
+[[fruit]]
+name = "apple"
+
+[fruit.physical]
+color = "red"
+
+[[fruit.variety]]
+name = "red delicious"
+
+[[fruit.variety]]
+name = "granny smith"
+
+[[fruit]]
+name = "banana"
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        products:
+          - name: Hammer
+            sku: 738594937
+          - {}
+          - name: Nail
+            color: gray
+
+        fruit:
+          - name: apple
+            physical:
+              color: red
+            variety:
+              - name: red delicious
+              - name: granny smith
+          - name: banana
+        ")
+    }
+
+    #[test]
+    fn test_display_yaml_empty() {
+        let s = r#"
+a = []
+b = {}
+c = [[], {}, [[]]]
+d = { e = [], f = {} }
+
+[g]
+
+[[h]]
+
+[i.j]
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        a: []
+
+        b: {}
+
+        c:
+          - []
+          - {}
+          - - []
+
+        d:
+          e: []
+          f: {}
+
+        g: {}
+
+        h:
+          - {}
+
+        i:
+          j: {}
+        ");
+
+        let options = YamlOptions {
+            document_start: true,
+            ..YamlOptions::default()
+        };
+        let r = ["", "# only a comment\n"].map(|s| {
+            let doc = parse(s).unwrap();
+            let yaml = |options| Yaml(&doc, options).to_string();
+            [yaml(&YamlOptions::default()), yaml(&options)]
+        });
+        assert_snapshot!(format!("{r:?}"), @r#"[["{}", "---\n{}"], ["{}", "---\n{}"]]"#)
+    }
+
+    #[test]
+    fn test_display_yaml_escapes() {
+        let s = r#"
+a = "say \"hi\" \\o/"
+b = "line\nnext\ttab\u0007bell"
+c = "\u00e9\U0001F600"
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r#"
+        a: say "hi" \o/
+        b: "line\nnext\ttab\u0007bell"
+        c: é😀
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_strings() {
+        let s = r##"
+a = ["yes", "no", "null", "~", "true", "On", "<<", ""]
+b = ["1.0", "0x10", "-12", "1_000", "1e3", ".inf", "-.NaN", "1:30", "2001-12-14"]
+c = ["- item", "-", "-foo", "a: b", "a:b", "b:", "#tag", "a #b", "a#b", "? x", "[x]", "*ref"]
+d = [" lead", "trail ", "---", "it's", "'q'", "\u2028", "\u0000", "10.0.0.1", "0xZZ"]
+"##;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r#"
+        a:
+          - 'yes'
+          - 'no'
+          - 'null'
+          - '~'
+          - 'true'
+          - 'On'
+          - '<<'
+          - ''
+        b:
+          - '1.0'
+          - '0x10'
+          - '-12'
+          - '1_000'
+          - '1e3'
+          - '.inf'
+          - '-.NaN'
+          - '1:30'
+          - '2001-12-14'
+        c:
+          - '- item'
+          - '-'
+          - -foo
+          - 'a: b'
+          - a:b
+          - 'b:'
+          - '#tag'
+          - 'a #b'
+          - a#b
+          - '? x'
+          - '[x]'
+          - '*ref'
+        d:
+          - ' lead'
+          - 'trail '
+          - '---'
+          - it's
+          - "'q'"
+          - "\u2028"
+          - "\0"
+          - 10.0.0.1
+          - 0xZZ
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_multiline_strings() {
+        let s = r#"
+strip = """
+one
+two"""
+clip = """
+one
+
+  two
+"""
+keep = """
+one
+two
+
+"""
+
+[t]
+list = ["one\ntwo\n", "  indented\nfirst line", "carriage\r\nreturn", "\n\n"]
+
+[u]
+keep = "a\n\n"
+
+[v]
+x = 1
+"#;
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r#"
+        strip: |-
+          one
+          two
+        clip: |
+          one
+
+            two
+        keep: |+
+          one
+          two
+
+        t:
+          list:
+            - |
+              one
+              two
+            - "  indented\nfirst line"
+            - "carriage\r\nreturn"
+            - "\n\n"
+
+        u:
+          keep: |+
+            a
+
+        v:
+          x: 1
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_flow() {
+        let s = r#"
+ports = [8000, 8001]
+data = [["delta", "phi"], [3.14, { a = 72.0, b = 26 }]]
+strings = ["a, b", "[c]", "d:e", "what?", "one\ntwo\n", "a long string to fold"]
+"{key}" = { "a,b" = 1, "k?" = 2, empty = [] }
+nested = [{ name = "a long name", tags = ["x", "y"] }]
+
+[table]
+a = 1
+"#;
+        let doc = parse(s).unwrap();
+        let r = [Flow::Width(20), Flow::Always].map(|flow| {
+            let options = YamlOptions {
+                line_width: Some(10),
+                flow,
+                ..YamlOptions::default()
+            };
+            Yaml(&doc, &options).to_string()
+        });
+
+        assert_snapshot!(r.join("\n\n"), @r#"
+        ports: [8000, 8001]
+        data:
+          - [delta, phi]
+          - - 3.14
+            - {a: 72.0, b: 26}
+        strings:
+          - a, b
+          - '[c]'
+          - d:e
+          - what?
+          - |
+            one
+            two
+          - a long
+            string
+            to
+            fold
+
+        '{key}':
+          a,b: 1
+          k?: 2
+          empty: []
+
+        nested:
+          - name: a
+              long
+              name
+            tags: [x, 'y']
+
+        table: {a: 1}
+
+        ports: [8000, 8001]
+        data: [[delta, phi], [3.14, {a: 72.0, b: 26}]]
+        strings: ['a, b', '[c]', 'd:e', 'what?', "one\ntwo\n", a long string to fold]
+        '{key}': {'a,b': 1, 'k?': 2, empty: []}
+        nested: [{name: a long name, tags: [x, 'y']}]
+        table: {a: 1}
+        "#)
+    }
+
+    #[test]
+    fn test_write_yaml() {
+        let s = r#"
+a = [[[{ b = "one\n\ntwo", c = [1, 2] }]]]
+d = { e = { f = "a long string to fold" } }
+"#;
+        let doc = parse(s).unwrap();
+        let options = YamlOptions {
+            line_width: Some(16),
+            ..YamlOptions::default()
+        };
+        let mut r = Vec::new();
+        write_yaml(&mut r, &doc, &options).unwrap();
+        let r = String::from_utf8(r).unwrap();
+        assert_eq!(r, Yaml(&doc, &options).to_string());
+
+        assert_snapshot!(r, @r"
+        a:
+          - - - b: |-
+                  one
+
+                  two
+                c:
+                  - 1
+                  - 2
+
+        d:
+          e:
+            f: a long
+              string to
+              fold
+        ")
+    }
+
+    #[test]
+    fn test_display_yaml_special_floats() {
+        let s = "a = inf\nb = +inf\nc = -inf\nd = nan\ne = -nan\nf = 1e300\ng = 0.0000001\nh = 1e22\ni = 123456789012345678.0\nj = -1.5e-10\nk = 1.0\n";
+        let doc = parse(s).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r"
+        a: .inf
+        b: .inf
+        c: -.inf
+        d: .nan
+        e: .nan
+        f: 1.0e+300
+        g: 1.0e-7
+        h: 1.0e+22
+        i: 1.2345678901234568e+17
+        j: -1.5e-10
+        k: 1.0
+        ")
+    }
+
+    const DATETIMES: &str = r#"
+odt1 = 1979-05-27T07:32:00Z
+odt2 = 1979-05-27T00:32:00.999999-07:00
+odt3 = 1979-05-27 07:32:00+05:30
+ldt = 1979-05-27t07:32:00.5
+ld = 1979-05-27
+lt = 00:32:00.999999
+"#;
+
+    #[test]
+    fn test_display_yaml_datetimes() {
+        let doc = parse(DATETIMES).unwrap();
+        let r = Yaml(&doc, &YamlOptions::default()).to_string();
+
+        assert_snapshot!(r, @r#"
+        odt1: 1979-05-27T07:32:00Z
+        odt2: 1979-05-27T00:32:00.999999-07:00
+        odt3: 1979-05-27T07:32:00+05:30
+        ldt: 1979-05-27T07:32:00.5
+        ld: 1979-05-27
+        lt: "00:32:00.999999"
+        "#)
+    }
+
+    #[test]
+    fn test_display_yaml_datetimes_strict() {
+        let doc = parse(DATETIMES).unwrap();
+        let options = YamlOptions {
+            strict: true,
+            ..YamlOptions::default()
+        };
+        let r = Yaml(&doc, &options).to_string();
+
+        assert_snapshot!(r, @r#"
+        odt1: "1979-05-27T07:32:00Z"
+        odt2: "1979-05-27T00:32:00.999999-07:00"
+        odt3: "1979-05-27T07:32:00+05:30"
+        ldt: "1979-05-27T07:32:00.5"
+        ld: "1979-05-27"
+        lt: "00:32:00.999999"
+        "#)
+    }
 }
@@ -1,8 +1,40 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+};
+
 #[derive(Debug, PartialEq)]
 pub struct Array(pub Vec<Value>);
 
-#[derive(Debug, PartialEq)]
-pub struct InlineTable(pub Vec<Pair>);
+// the pairs in the order they were written, along with the index of the first
+// pair of each key so that looking a key up doesn't go through all of them
+#[derive(Default)]
+pub struct InlineTable {
+    pairs: Vec<Pair>,
+    index: HashMap<Identifier, usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Offset {
+    Z,
+    // minutes east of UTC
+    Minutes(i16),
+}
 
 #[derive(Debug, PartialEq)]
 pub enum Value {
@@ -10,11 +42,15 @@ pub enum Value {
     Float(f64),
     Boolean(bool),
     String(String),
+    OffsetDateTime(Date, Time, Offset),
+    LocalDateTime(Date, Time),
+    LocalDate(Date),
+    LocalTime(Time),
     Array(Array),
     InlineTable(InlineTable),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Identifier(pub String);
 
 #[derive(Debug, PartialEq)]
@@ -23,11 +59,187 @@ pub struct Pair {
     pub value: Value,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum Header {
+    Table(Vec<Identifier>),
+    ArrayOfTables(Vec<Identifier>),
+}
+
+// a `[table]` or `[[array]]` section as written in the document
 #[derive(Debug, PartialEq)]
 pub struct Table {
-    pub header: Identifier,
+    pub header: Header,
     pub body: InlineTable,
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Document(pub Vec<Table>);
+// the top-level table, holding the top-level pairs and every section
+#[derive(Debug, PartialEq, Default)]
+pub struct Document(pub InlineTable);
+
+impl Value {
+    // inline tables and arrays of tables, both render as nested mappings
+    pub fn is_table(&self) -> bool {
+        match self {
+            Self::InlineTable(_) => true,
+            Self::Array(Array(vec)) => {
+                !vec.is_empty() && vec.iter().all(|v| matches!(v, Self::InlineTable(_)))
+            }
+            _ => false,
+        }
+    }
+
+    // the value under a key of a table, or under an index of an array
+    pub fn get(&self, key: &Identifier) -> Option<&Value> {
+        match self {
+            Self::InlineTable(table) => table.get(key),
+            Self::Array(Array(vec)) => vec.get(key.0.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+}
+
+impl InlineTable {
+    pub fn pairs(&self) -> &[Pair] {
+        &self.pairs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn get(&self, key: &Identifier) -> Option<&Value> {
+        self.index.get(key).map(|&i| &self.pairs[i].value)
+    }
+
+    fn get_mut(&mut self, key: &Identifier) -> Option<&mut Pair> {
+        self.index.get(key).map(|&i| &mut self.pairs[i])
+    }
+
+    fn push(&mut self, pair: Pair) {
+        self.index
+            .entry(pair.key.clone())
+            .or_insert(self.pairs.len());
+        self.pairs.push(pair);
+    }
+
+    // merges the pair into an existing table with the same key so that
+    // dotted keys like `a.b = 1` and `a.c = 2` end up under a single `a`
+    pub fn insert(&mut self, pair: Pair) {
+        let existing = self.get_mut(&pair.key);
+        match (existing, pair.value) {
+            (
+                Some(Pair {
+                    value: Value::InlineTable(table),
+                    ..
+                }),
+                Value::InlineTable(other),
+            ) => table.extend(other),
+            (_, value) => self.push(Pair {
+                key: pair.key,
+                value,
+            }),
+        }
+    }
+
+    // returns the table under the key, which is the last element in case of
+    // an array of tables, and creates an empty one if there's none
+    pub fn table_mut(&mut self, key: &Identifier) -> &mut InlineTable {
+        let index = match self.index.get(key) {
+            Some(&i) if self.pairs[i].value.is_table() => Some(i),
+            // a key defined twice, which is an error that's still recovered from
+            Some(_) => (self.pairs.iter()).position(|p| &p.key == key && p.value.is_table()),
+            None => None,
+        };
+        let index = match index {
+            Some(i) => i,
+            None => {
+                let value = Value::InlineTable(InlineTable::default());
+                self.push(Pair {
+                    key: key.clone(),
+                    value,
+                });
+                self.pairs.len() - 1
+            }
+        };
+
+        match &mut self.pairs[index].value {
+            Value::InlineTable(table) => table,
+            Value::Array(Array(vec)) => match vec.last_mut() {
+                Some(Value::InlineTable(table)) => table,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    // appends the table to the array of tables under the key
+    pub fn push_table(&mut self, key: Identifier, table: InlineTable) {
+        let value = Value::InlineTable(table);
+        let existing = self.get_mut(&key);
+        match existing {
+            Some(Pair {
+                value: Value::Array(Array(vec)),
+                ..
+            }) => vec.push(value),
+            _ => self.push(Pair {
+                key,
+                value: Value::Array(Array(vec![value])),
+            }),
+        }
+    }
+}
+
+impl Debug for InlineTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InlineTable").field(&self.pairs).finish()
+    }
+}
+
+impl PartialEq for InlineTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.pairs == other.pairs
+    }
+}
+
+impl IntoIterator for InlineTable {
+    type Item = Pair;
+    type IntoIter = std::vec::IntoIter<Pair>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl Extend<Pair> for InlineTable {
+    fn extend<I: IntoIterator<Item = Pair>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|p| self.insert(p));
+    }
+}
+
+impl FromIterator<Pair> for InlineTable {
+    fn from_iter<I: IntoIterator<Item = Pair>>(iter: I) -> Self {
+        let mut table = Self::default();
+        table.extend(iter);
+        table
+    }
+}
+
+impl Document {
+    // merges the section into the tables defined before it, so that
+    // `[a.b]` and `[a.c]` end up under a single `a`
+    pub fn insert(&mut self, table: Table) {
+        let Table { header, body } = table;
+        let (mut keys, is_array) = match header {
+            Header::Table(keys) => (keys, false),
+            Header::ArrayOfTables(keys) => (keys, true),
+        };
+        let key = keys.pop().expect("table header is never empty");
+        let parent = (keys.iter()).fold(&mut self.0, |table, key| table.table_mut(key));
+
+        if is_array {
+            parent.push_table(key, body);
+        } else {
+            parent.table_mut(&key).extend(body);
+        }
+    }
+}
@@ -1,20 +1,823 @@
-use anyhow::Result;
-use std::{fs::File, io::Read};
+use batch::Source;
+use clap::{
+    error::ErrorKind as ClapErrorKind, parser::ValueSource, value_parser, ArgMatches, Args,
+    CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
+use diagnostic::{Diagnostic, Kind, Title};
+use formatter::Toml;
+use generator::{Flow, QuoteStyle, Yaml, YamlOptions};
+use ir::{Document, Identifier, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+};
+use watch::Stamps;
 
+mod batch;
+mod diagnostic;
+mod formatter;
 mod generator;
 mod ir;
+mod output;
 mod parser;
+mod validator;
+mod watch;
 
-fn main() -> Result<()> {
-    if let Some(path) = std::env::args().nth(1) {
-        let mut s = String::new();
-        File::open(path)?.read_to_string(&mut s)?;
+// so that scripts can tell the kinds of failures apart, clap exits with 2 on
+// invalid arguments as well
+const EXIT_USAGE: u8 = 2;
+const EXIT_IO: u8 = 3;
+const EXIT_SYNTAX: u8 = 4;
+const EXIT_SEMANTIC: u8 = 5;
+const EXIT_NOT_FOUND: u8 = 6;
 
-        match parser::parse(&s) {
-            Ok(doc) => println!("{doc}"),
-            Err(err) => eprintln!("{err}"),
+const EXIT_CODES: &str = "\
+Exit codes:
+  0  Success
+  2  Invalid arguments
+  3  A file can't be read or written
+  4  The TOML has syntax errors
+  5  The TOML is invalid, like a key defined twice
+  6  `get` found no value under the key";
+
+/// Converts TOML to YAML
+#[derive(Parser)]
+#[command(
+    version,
+    about,
+    after_help = EXIT_CODES,
+    subcommand_precedence_over_arg = true
+)]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // the arguments of `convert`, which runs when no command is given
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+#[derive(Args)]
+struct GlobalArgs {
+    /// When to color error messages
+    #[arg(long, value_enum, default_value_t = Color::Auto, global = true)]
+    color: Color,
+
+    /// Print no error messages, only exit with a non-zero code
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert TOML to YAML, the default command
+    Convert(ConvertArgs),
+    /// Check that the TOML is valid without writing anything
+    Check(InputArgs),
+    /// Reformat the TOML, comments are not kept
+    Fmt(FmtArgs),
+    /// Print the value under a dotted key
+    Get(GetArgs),
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// The TOML file, the standard input is read when it's `-` or missing
+    path: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// The TOML files, directories or glob patterns, the standard input is
+    /// read when it's `-` or missing
+    paths: Vec<PathBuf>,
+
+    /// Write the YAML to a file instead of the standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Write the YAML next to the input, `foo.toml` to `foo.yaml`
+    #[arg(long, conflicts_with = "output")]
+    in_place: bool,
+
+    /// Write the YAML files to a directory that mirrors the input tree
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "in_place"])]
+    out_dir: Option<PathBuf>,
+
+    /// Convert again whenever the inputs change, until interrupted
+    #[arg(long)]
+    watch: bool,
+
+    #[command(flatten)]
+    yaml: YamlArgs,
+}
+
+#[derive(Args)]
+#[command(next_help_heading = "YAML options")]
+struct YamlArgs {
+    /// The spaces for each level of nesting
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = value_parser!(u8).range(2..=8))]
+    indent: u8,
+
+    /// Write sequences flush with their key instead of indented under it
+    #[arg(long)]
+    flush_sequences: bool,
+
+    /// The empty lines around top-level tables
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    blank_lines: usize,
+
+    /// Start the document with `---`
+    #[arg(long)]
+    document_start: bool,
+
+    /// The quotes for strings that need them
+    #[arg(long, value_enum, default_value_t = Quote::Single)]
+    quote: Quote,
+
+    /// Fold long strings at spaces to fit in the width
+    #[arg(long, value_name = "N")]
+    line_width: Option<usize>,
+
+    /// Quote dates and times instead of writing YAML timestamps
+    #[arg(long)]
+    strict: bool,
+
+    /// Write arrays and tables as `[a, b]` and `{a: 1}` when that fits in the width
+    #[arg(long, value_name = "N", conflicts_with = "flow")]
+    flow_width: Option<usize>,
+
+    /// Write all nested arrays and tables as `[a, b]` and `{a: 1}`
+    #[arg(long)]
+    flow: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Quote {
+    Single,
+    Double,
+}
+
+#[derive(Args)]
+struct FmtArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Write the TOML to a file instead of the standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Overwrite the input with the formatted TOML, which needs `--drop-comments`
+    #[arg(long, conflicts_with = "output")]
+    in_place: bool,
+
+    /// Allow overwriting the input, losing its comments and layout
+    #[arg(long)]
+    drop_comments: bool,
+}
+
+#[derive(Args)]
+struct GetArgs {
+    /// The key, like `servers.alpha.ip`, or `products.0.name` for arrays
+    key: String,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+// a failure that has already been reported, holding the exit code
+struct Exit(u8);
+
+// why a file of a batch wasn't converted, reported once all files are done
+enum Failure {
+    Error(u8, String),
+    Invalid(Vec<Diagnostic>),
+}
+
+type Result<T> = std::result::Result<T, Exit>;
+
+impl GlobalArgs {
+    fn color(&self) -> bool {
+        match self.color {
+            Color::Always => true,
+            Color::Never => false,
+            Color::Auto => io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+
+    fn error(&self, code: u8, message: &str) -> Exit {
+        self.status("error", message);
+        Exit(code)
+    }
+
+    // prints the diagnostics and returns the exit code of the worst of them,
+    // syntax errors being worse than semantic ones
+    fn report(&self, diagnostics: &[Diagnostic]) -> Exit {
+        for diagnostic in diagnostics.iter().filter(|_| !self.quiet) {
+            if self.color() {
+                eprintln!("{diagnostic:#}\n");
+            } else {
+                eprintln!("{diagnostic}\n");
+            }
+        }
+        if diagnostics.iter().any(|d| d.kind == Kind::Syntax) {
+            Exit(EXIT_SYNTAX)
+        } else {
+            Exit(EXIT_SEMANTIC)
+        }
+    }
+
+    fn read(&self, input: &InputArgs) -> Result<String> {
+        let s = match input.path.as_deref() {
+            // nobody types TOML into a terminal, it's more likely a missing path
+            None if io::stdin().is_terminal() => {
+                let message = "no input, pass a path or `-` for the standard input";
+                let kind = ClapErrorKind::MissingRequiredArgument;
+                Cli::command().error(kind, message).exit()
+            }
+            None => io::read_to_string(io::stdin()),
+            Some(path) if path == Path::new("-") => io::read_to_string(io::stdin()),
+            Some(path) => fs::read_to_string(path),
+        };
+        s.map_err(|err| {
+            let name = input.path.as_deref().unwrap_or(Path::new("-"));
+            let message = format!("can't read `{}`: {err}", name.display());
+            self.error(EXIT_IO, &message)
+        })
+    }
+
+    fn parse(&self, input: &InputArgs) -> Result<Document> {
+        let s = self.read(input)?;
+        parser::parse(&s).map_err(|diagnostics| match input.path.as_deref() {
+            Some(path) if path != Path::new("-") => {
+                let diagnostics: Vec<_> =
+                    diagnostics.into_iter().map(|d| d.with_path(path)).collect();
+                self.report(&diagnostics)
+            }
+            _ => self.report(&diagnostics),
+        })
+    }
+
+    fn status(&self, level: &str, message: &str) {
+        let title = Title { level, message };
+        match (self.quiet, self.color()) {
+            (true, _) => {}
+            (false, true) => eprintln!("{title:#}"),
+            (false, false) => eprintln!("{title}"),
+        }
+    }
+
+    // writes to the standard output when there's no path
+    fn write(&self, path: Option<&Path>, s: &str) -> Result<()> {
+        let written = match path {
+            Some(path) => output::write_atomic(path, s),
+            None => io::stdout().write_all(s.as_bytes()),
+        };
+        written.map_err(|err| {
+            let name = path.unwrap_or(Path::new("-"));
+            let message = format!("can't write `{}`: {err}", name.display());
+            self.error(EXIT_IO, &message)
+        })
+    }
+
+    // returns the output path of `--in-place`, which needs an input path
+    fn in_place(&self, input: &InputArgs, output: fn(&Path) -> PathBuf) -> Result<PathBuf> {
+        match input.path.as_deref() {
+            Some(path) if path != Path::new("-") => Ok(output(path)),
+            _ => Err(self.error(EXIT_USAGE, "`--in-place` needs an input path")),
+        }
+    }
+}
+
+impl YamlArgs {
+    fn options(&self) -> YamlOptions {
+        let quote_style = match self.quote {
+            Quote::Single => QuoteStyle::Single,
+            Quote::Double => QuoteStyle::Double,
+        };
+        let flow = match (self.flow, self.flow_width) {
+            (true, _) => Flow::Always,
+            (false, Some(width)) => Flow::Width(width),
+            (false, None) => Flow::Never,
+        };
+        YamlOptions {
+            indent: self.indent.into(),
+            indent_sequences: !self.flush_sequences,
+            blank_lines: self.blank_lines,
+            document_start: self.document_start,
+            quote_style,
+            line_width: self.line_width,
+            strict: self.strict,
+            flow,
+        }
+    }
+}
+
+fn to_yaml(doc: &Document, options: &YamlOptions) -> String {
+    format!("{}\n", Yaml(doc, options))
+}
+
+// writes the YAML to the standard output as it's generated
+fn print_yaml(doc: &Document, options: &YamlOptions) -> io::Result<()> {
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    generator::write_yaml(&mut stdout, doc, options)?;
+    stdout.write_all(b"\n")?;
+    stdout.flush()
+}
+
+fn convert(global: &GlobalArgs, args: ConvertArgs) -> Result<()> {
+    if args.watch {
+        return watch(global, args);
+    }
+    if is_batch(&args) {
+        return convert_batch(global, args);
+    }
+
+    let input = InputArgs {
+        path: args.paths.into_iter().next(),
+    };
+    let output = match args.in_place {
+        true => Some(global.in_place(&input, output::sibling)?),
+        false => args.output,
+    };
+    if let (Some(input), Some(output)) = (input.path.as_deref(), output.as_deref()) {
+        if output::is_same_file(input, output) {
+            let message = format!("`{}` would be overwritten by its output", input.display());
+            return Err(global.error(EXIT_USAGE, &message));
         }
     }
 
+    let doc = global.parse(&input)?;
+    let options = args.yaml.options();
+    match output {
+        Some(path) => global.write(Some(&path), &to_yaml(&doc, &options)),
+        None => print_yaml(&doc, &options)
+            .map_err(|err| global.error(EXIT_IO, &format!("can't write `-`: {err}"))),
+    }
+}
+
+fn is_batch(args: &ConvertArgs) -> bool {
+    args.out_dir.is_some()
+        || args.paths.len() > 1
+        || args
+            .paths
+            .iter()
+            .any(|p| p.is_dir() || batch::is_pattern(p))
+}
+
+fn check_batch(global: &GlobalArgs, args: &ConvertArgs) -> Result<()> {
+    if args.output.is_some() {
+        let message = "`--output` takes a single input, use `--out-dir` for several";
+        return Err(global.error(EXIT_USAGE, message));
+    }
+    if args.out_dir.is_none() && !args.in_place {
+        let message = "several inputs need `--out-dir` or `--in-place`";
+        return Err(global.error(EXIT_USAGE, message));
+    }
     Ok(())
 }
+
+// where the YAML of a file goes, which is the standard output when there's
+// no path
+fn output_of(args: &ConvertArgs, source: &Source) -> Option<PathBuf> {
+    match (&args.out_dir, args.in_place) {
+        (Some(dir), _) => Some(source.mirror(dir)),
+        (None, true) => Some(output::sibling(&source.path)),
+        (None, false) => args.output.clone(),
+    }
+}
+
+fn conflicts(args: &ConvertArgs, sources: &[Source]) -> HashMap<PathBuf, String> {
+    let outputs: Vec<_> = sources
+        .iter()
+        .filter_map(|source| Some((source.path.as_path(), output_of(args, source)?)))
+        .collect();
+    batch::conflicts(&outputs)
+}
+
+fn convert_batch(global: &GlobalArgs, args: ConvertArgs) -> Result<()> {
+    if args.paths.iter().any(|p| p == Path::new("-")) {
+        let message = "the standard input can't be converted along with other inputs";
+        return Err(global.error(EXIT_USAGE, message));
+    }
+    check_batch(global, &args)?;
+
+    let sources = batch::expand(&args.paths).map_err(|message| global.error(EXIT_IO, &message))?;
+    let conflicts = conflicts(&args, &sources);
+    let (exit, failed) = convert_files(global, &args, &sources, &conflicts);
+    let message = format!("{} converted, {failed} failed", sources.len() - failed);
+    global.status("summary", &message);
+    exit.map_or(Ok(()), Err)
+}
+
+// polls the inputs and converts the files that changed, forever. Errors are
+// reported like in a single run, but don't stop the watching.
+fn watch(global: &GlobalArgs, args: ConvertArgs) -> Result<()> {
+    if args.paths.is_empty() || args.paths.iter().any(|p| p == Path::new("-")) {
+        let message = "`--watch` needs input paths, the standard input can't be watched";
+        return Err(global.error(EXIT_USAGE, message));
+    }
+    if is_batch(&args) {
+        check_batch(global, &args)?;
+    }
+
+    let mut stamps = Stamps::default();
+    let mut last_error = None;
+    loop {
+        match batch::expand(&args.paths) {
+            Ok(mut sources) => {
+                last_error = None;
+                // from all the inputs, since an unchanged one still has its output
+                let conflicts = conflicts(&args, &sources);
+                stamps.retain_changed(&mut sources);
+                convert_files(global, &args, &sources, &conflicts);
+            }
+            // shown once instead of on every poll, like a pattern that
+            // matches nothing until a file is created
+            Err(message) if last_error.as_ref() != Some(&message) => {
+                global.error(EXIT_IO, &message);
+                last_error = Some(message);
+            }
+            Err(_) => {}
+        }
+        thread::sleep(watch::POLL_INTERVAL);
+    }
+}
+
+// converts the files in parallel, then reports the errors and the status of
+// every file in their order, so that the messages don't interleave. Returns
+// the first failure and the number of failures.
+fn convert_files(
+    global: &GlobalArgs,
+    args: &ConvertArgs,
+    sources: &[Source],
+    conflicts: &HashMap<PathBuf, String>,
+) -> (Option<Exit>, usize) {
+    let options = args.yaml.options();
+    let results = batch::parallel(sources, |source| {
+        if let Some(message) = conflicts.get(&source.path) {
+            return Err(Failure::Error(EXIT_USAGE, message.clone()));
+        }
+        let output = output_of(args, source);
+        convert_file(&source.path, output.as_deref(), &options).map(|written| (output, written))
+    });
+
+    let mut exit = None;
+    let mut failed = 0;
+    let mut statuses = Vec::new();
+    for (source, result) in sources.iter().zip(results) {
+        let input = source.path.display();
+        match result {
+            Ok((output, written)) => {
+                let level = if written { "converted" } else { "unchanged" };
+                let output = output.as_deref().unwrap_or(Path::new("-"));
+                statuses.push((level, format!("{input} -> {}", output.display())));
+            }
+            Err(failure) => {
+                let failure = match failure {
+                    Failure::Error(code, message) => global.error(code, &message),
+                    Failure::Invalid(diagnostics) => global.report(&diagnostics),
+                };
+                exit.get_or_insert(failure);
+                failed += 1;
+                statuses.push(("failed", input.to_string()));
+            }
+        }
+    }
+
+    for (level, message) in &statuses {
+        global.status(level, message);
+    }
+    (exit, failed)
+}
+
+// returns whether the output was written, which it isn't when it's a file
+// that already has the YAML
+fn convert_file(
+    path: &Path,
+    output: Option<&Path>,
+    options: &YamlOptions,
+) -> std::result::Result<bool, Failure> {
+    if output.is_some_and(|output| output::is_same_file(path, output)) {
+        let message = format!("`{}` would be overwritten by its output", path.display());
+        return Err(Failure::Error(EXIT_USAGE, message));
+    }
+    let s = fs::read_to_string(path).map_err(|err| {
+        let message = format!("can't read `{}`: {err}", path.display());
+        Failure::Error(EXIT_IO, message)
+    })?;
+    let doc = parser::parse(&s).map_err(|diagnostics| {
+        let diagnostics = diagnostics.into_iter().map(|d| d.with_path(path));
+        Failure::Invalid(diagnostics.collect())
+    })?;
+
+    let written = match output {
+        Some(output) => {
+            let parent = output.parent().unwrap_or(Path::new(""));
+            let yaml = to_yaml(&doc, options);
+            fs::create_dir_all(parent).and_then(|()| output::write_if_changed(output, &yaml))
+        }
+        None => print_yaml(&doc, options).map(|()| true),
+    };
+    written.map_err(|err| {
+        let name = output.unwrap_or(Path::new("-"));
+        let message = format!("can't write `{}`: {err}", name.display());
+        Failure::Error(EXIT_IO, message)
+    })
+}
+
+fn check(global: &GlobalArgs, args: InputArgs) -> Result<()> {
+    global.parse(&args).map(|_| ())
+}
+
+fn fmt(global: &GlobalArgs, args: FmtArgs) -> Result<()> {
+    let output = match args.in_place {
+        true => Some(global.in_place(&args.input, Path::to_path_buf)?),
+        false => args.output,
+    };
+    // the formatted TOML is all that would be left of the file
+    let overwrites = match (args.input.path.as_deref(), output.as_deref()) {
+        (Some(input), Some(output)) => output::is_same_file(input, output),
+        _ => false,
+    };
+    if overwrites && !args.drop_comments {
+        let message = "overwriting the input loses its comments and layout, pass `--drop-comments` to do it anyway";
+        return Err(global.error(EXIT_USAGE, message));
+    }
+
+    let doc = global.parse(&args.input)?;
+    global.write(output.as_deref(), &format!("{}\n", Toml(&doc)))
+}
+
+fn get(global: &GlobalArgs, args: GetArgs) -> Result<()> {
+    let Some(keys) = parser::parse_query(&args.key) else {
+        let message = format!("invalid key `{}`", args.key);
+        return Err(global.error(EXIT_USAGE, &message));
+    };
+    let doc = global.parse(&args.input)?;
+    let Some(value) = lookup(&doc, &keys) else {
+        let message = format!("key `{}` not found", args.key);
+        return Err(global.error(EXIT_NOT_FOUND, &message));
+    };
+
+    // strings are written as they are, for use in shell scripts
+    let s = match value {
+        Value::String(s) => s.clone(),
+        value => Yaml(value, &YamlOptions::default()).to_string(),
+    };
+    global.write(None, &format!("{s}\n"))
+}
+
+// the value under the keys, where numbers are indices in arrays
+fn lookup<'a>(doc: &'a Document, keys: &[Identifier]) -> Option<&'a Value> {
+    let (first, rest) = keys.split_first().expect("key is never empty");
+    let value = doc.0.get(first)?;
+    rest.iter().try_fold(value, |v, key| v.get(key))
+}
+
+// the arguments of `convert` given before another command, which would be
+// ignored otherwise
+fn check_command(matches: &ArgMatches) -> std::result::Result<(), clap::Error> {
+    let Some((name, _)) = matches.subcommand() else {
+        return Ok(());
+    };
+    let convert = ConvertArgs::augment_args(clap::Command::new("convert"));
+    let given = convert
+        .get_arguments()
+        .find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine));
+    if let Some(given) = given {
+        // the built command has what's needed to display the argument
+        let mut cli = Cli::command();
+        cli.build();
+        let arg = cli
+            .get_arguments()
+            .find(|arg| arg.get_id() == given.get_id());
+        let message = format!(
+            "the argument '{}' cannot be used with the '{name}' command",
+            arg.unwrap_or(given)
+        );
+        return Err(cli.error(ClapErrorKind::ArgumentConflict, message));
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let matches = Cli::command().get_matches();
+    check_command(&matches).unwrap_or_else(|err| err.exit());
+    let Cli {
+        global,
+        command,
+        convert: args,
+    } = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    let result = match command {
+        None => convert(&global, args),
+        Some(Command::Convert(args)) => convert(&global, args),
+        Some(Command::Check(args)) => check(&global, args),
+        Some(Command::Fmt(args)) => fmt(&global, args),
+        Some(Command::Get(args)) => get(&global, args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Exit(code)) => ExitCode::from(code),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_compact_debug_snapshot;
+    use output::TempDir;
+
+    fn quiet() -> GlobalArgs {
+        GlobalArgs {
+            color: Color::Never,
+            quiet: true,
+        }
+    }
+
+    #[test]
+    fn test_convert_files_same_output() {
+        let dir = TempDir::new("watch-same");
+        let path = dir.join("foo.toml");
+        fs::write(&path, "a = 1\n").unwrap();
+
+        let output = dir.join(".").join("foo.toml");
+        let args = [
+            "toml-to-yaml".into(),
+            "--watch".into(),
+            "-o".into(),
+            output,
+            path.clone(),
+        ];
+        let args = Cli::try_parse_from(args).unwrap().convert;
+        let sources = [Source {
+            path: path.clone(),
+            base: dir.to_path_buf(),
+        }];
+        // the check before converting, as done on every poll, and the one of
+        // each file on its own
+        let r = [conflicts(&args, &sources), HashMap::new()].map(|conflicts| {
+            let (exit, failed) = convert_files(&quiet(), &args, &sources, &conflicts);
+            (exit.map(|Exit(code)| code), failed)
+        });
+        let toml = fs::read_to_string(&path).unwrap();
+        assert_compact_debug_snapshot!((r, toml), @r#"([(Some(2), 1), (Some(2), 1)], "a = 1\n")"#)
+    }
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_cli_commands() {
+        let r = [
+            vec!["a.toml"],
+            vec!["convert", "--indent", "4", "a.toml"],
+            vec!["check", "a.toml"],
+            vec!["fmt", "--in-place", "--drop-comments", "a.toml"],
+            vec!["get", "a.b", "a.toml"],
+            vec!["--quiet", "check"],
+        ]
+        .map(|args| {
+            let cli = Cli::try_parse_from(["toml-to-yaml"].into_iter().chain(args)).unwrap();
+            match cli.command {
+                None => format!("convert {:?}", cli.convert.paths),
+                Some(Command::Convert(args)) => {
+                    format!("convert {:?} {}", args.paths, args.yaml.indent)
+                }
+                Some(Command::Check(args)) => format!("check {:?} {}", args.path, cli.global.quiet),
+                Some(Command::Fmt(args)) => format!("fmt {:?} {}", args.input.path, args.in_place),
+                Some(Command::Get(args)) => format!("get {} {:?}", args.key, args.input.path),
+            }
+        });
+        assert_compact_debug_snapshot!(r, @r#"["convert [\"a.toml\"]", "convert [\"a.toml\"] 4", "check Some(\"a.toml\") false", "fmt Some(\"a.toml\") true", "get a.b Some(\"a.toml\")", "check None true"]"#)
+    }
+
+    #[test]
+    fn test_check_command() {
+        let r = [
+            vec!["--indent", "4", "check", "a.toml"],
+            vec!["-o", "b.yaml", "get", "a", "a.toml"],
+            vec!["--quiet", "check", "a.toml"],
+            vec!["--indent", "4", "a.toml"],
+        ]
+        .map(|args| {
+            let args = ["toml-to-yaml"].into_iter().chain(args);
+            let matches = Cli::command().try_get_matches_from(args).unwrap();
+            check_command(&matches).map_err(|err| err.kind())
+        });
+        assert_compact_debug_snapshot!(r, @"[Err(ArgumentConflict), Err(ArgumentConflict), Ok(()), Ok(())]")
+    }
+
+    #[test]
+    fn test_lookup() {
+        let doc = parser::parse(
+            r#"
+title = "a"
+[[products]]
+name = "Hammer"
+[[products]]
+name = "Nail"
+sizes = [1, 2]
+"#,
+        )
+        .unwrap();
+        let r = [
+            "title",
+            "products.0.name",
+            "products.1.sizes.1",
+            "products.2.name",
+            "products.name",
+            "title.0",
+            "missing",
+        ]
+        .map(|key| {
+            let keys = parser::parse_query(key).unwrap();
+            lookup(&doc, &keys).map(|v| Yaml(v, &YamlOptions::default()).to_string())
+        });
+        assert_compact_debug_snapshot!(r, @r#"[Some("a"), Some("Hammer"), Some("2"), None, None, None, None]"#)
+    }
+
+    #[test]
+    fn test_get_not_found() {
+        let dir = TempDir::new("get");
+        let path = dir.join("a.toml");
+        fs::write(&path, "a = { b = 1 }").unwrap();
+
+        let r = ["a.c", "a..b"].map(|key| {
+            let args = GetArgs {
+                key: key.to_string(),
+                input: InputArgs {
+                    path: Some(path.clone()),
+                },
+            };
+            get(&quiet(), args).err().map(|Exit(code)| code)
+        });
+        assert_compact_debug_snapshot!(r, @"[Some(6), Some(2)]")
+    }
+
+    #[test]
+    fn test_report() {
+        let diagnostic = |kind| Diagnostic::new("a = 1", 0..1, kind, "");
+        let r = [
+            vec![diagnostic(Kind::Syntax)],
+            vec![diagnostic(Kind::Semantic)],
+            vec![diagnostic(Kind::Semantic), diagnostic(Kind::Syntax)],
+        ]
+        .map(|diagnostics| quiet().report(&diagnostics).0);
+        assert_compact_debug_snapshot!(r, @"[4, 5, 4]")
+    }
+
+    #[test]
+    fn test_parse_exit_codes() {
+        let dir = TempDir::new("exit");
+        let input = |name: &str, s: &str| {
+            let path = dir.join(name);
+            fs::write(&path, s).unwrap();
+            InputArgs { path: Some(path) }
+        };
+        let missing = InputArgs {
+            path: Some(dir.join("missing.toml")),
+        };
+        let r = [
+            input("valid.toml", "a = 1"),
+            input("syntax.toml", "a = "),
+            input("semantic.toml", "a = 1\na = 2"),
+            missing,
+        ]
+        .map(|input| quiet().parse(&input).err().map(|Exit(code)| code));
+        assert_compact_debug_snapshot!(r, @"[None, Some(4), Some(5), Some(3)]")
+    }
+
+    #[test]
+    fn test_convert_same_output() {
+        let dir = TempDir::new("convert");
+        let path = dir.join("foo.toml");
+        fs::write(&path, "a = 1\n").unwrap();
+
+        let convert_to = |output: PathBuf| {
+            let args = ["toml-to-yaml".into(), "-o".into(), output, path.clone()];
+            let args = Cli::try_parse_from(args).unwrap().convert;
+            convert(&quiet(), args).err().map(|Exit(code)| code)
+        };
+        let r = [path.clone(), dir.join(".").join("foo.toml")].map(convert_to);
+        let toml = fs::read_to_string(&path).unwrap();
+        assert_compact_debug_snapshot!((r, toml), @r#"([Some(2), Some(2)], "a = 1\n")"#)
+    }
+}
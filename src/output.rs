@@ -0,0 +1,152 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// the YAML file next to a TOML file, like `foo.yaml` for `foo.toml`
+pub fn sibling(path: &Path) -> PathBuf {
+    path.with_extension("yaml")
+}
+
+// writes to a temporary file next to the path and renames it over the path,
+// so that readers never see a partially written file
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    // each write has its own temporary file, even from threads of one process
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(".{name}.{}.{count}.tmp", std::process::id()));
+
+    let write = || {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+// the path without `.`, `..` and links, which for a file that doesn't exist
+// yet is resolved up to its directory
+pub fn resolve(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    match (fs::canonicalize(parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+// whether the paths lead to the same file, even when they're written
+// differently
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    resolve(a) == resolve(b)
+}
+
+// leaves the file alone when it already has the contents, so that whatever
+// watches it isn't triggered for nothing. Returns whether it was written.
+pub fn write_if_changed(path: &Path, contents: &str) -> io::Result<bool> {
+    match fs::read(path) {
+        Ok(old) if old == contents.as_bytes() => Ok(false),
+        _ => write_atomic(path, contents).map(|()| true),
+    }
+}
+
+// a directory for the files of a test, removed when it's dropped so that a
+// failing test doesn't leave it behind
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let dir = format!("toml-to-yaml-{name}-{}-{count}", std::process::id());
+        let dir = std::env::temp_dir().join(dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_compact_debug_snapshot;
+
+    #[test]
+    fn test_sibling() {
+        let r = ["config/foo.toml", "foo", "foo.bar.toml"].map(|p| sibling(Path::new(p)));
+        assert_compact_debug_snapshot!(r, @r#"["config/foo.yaml", "foo.yaml", "foo.bar.yaml"]"#)
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("out.yaml");
+
+        write_atomic(&path, "a: 1\n").unwrap();
+        write_atomic(&path, "a: 2\n").unwrap();
+        let r = (
+            fs::read_to_string(&path).unwrap(),
+            fs::read_dir(&dir).unwrap().count(),
+        );
+        assert_compact_debug_snapshot!(r, @r#"("a: 2\n", 1)"#)
+    }
+
+    #[test]
+    fn test_is_same_file() {
+        let dir = TempDir::new("same");
+        let path = dir.join("a.toml");
+        fs::write(&path, "").unwrap();
+
+        let r = [
+            is_same_file(&path, &dir.join(".").join("a.toml")),
+            is_same_file(&path, &dir.join("b.toml")),
+            is_same_file(Path::new("missing.toml"), Path::new("missing.toml")),
+            is_same_file(&dir.join("b.toml"), &dir.join(".").join("b.toml")),
+        ];
+        assert_compact_debug_snapshot!(r, @"[true, false, true, true]")
+    }
+
+    #[test]
+    fn test_write_if_changed() {
+        let dir = TempDir::new("changed");
+        let path = dir.join("out.yaml");
+
+        let r = ["a: 1\n", "a: 1\n", "a: 2\n"].map(|s| write_if_changed(&path, s).unwrap());
+        assert_compact_debug_snapshot!(r, @"[true, false, true]")
+    }
+}
@@ -1,18 +1,180 @@
-use crate::ir::{Array, Document, Identifier, InlineTable, Pair, Table, Value};
+use crate::{
+    diagnostic::{Diagnostic, Kind as DiagnosticKind},
+    ir::{
+        Array, Date, Document, Header, Identifier, InlineTable, Offset, Pair, Table, Time, Value,
+    },
+    validator::Validator,
+};
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_till},
-    character::complete::{alphanumeric1, char, digit1, multispace0, newline, space0},
-    combinator::{eof, map_res, not, opt},
-    error::Error as NomError,
-    multi::{fold_many0, fold_many1, separated_list0},
-    number::complete::double,
-    sequence::{delimited, pair, separated_pair, tuple},
-    Finish, IResult, Parser,
+    bytes::complete::{tag, take_till, take_while1, take_while_m_n},
+    character::complete::{
+        alphanumeric1, char, line_ending, multispace0, multispace1, not_line_ending, one_of, space0,
+    },
+    combinator::{cut, eof, map_opt, not, opt, peek, recognize, value},
+    error::{context, ContextError, ErrorKind as NomErrorKind, ParseError},
+    multi::{fold_many0, fold_many1, separated_list0, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    Err as NomErr, Offset as _, Parser,
+};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
 };
 
+// semantic errors hold the input where the clashing key was first defined
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind<I> {
+    Nom(NomErrorKind),
+    // what was expected instead of the input, like "a value"
+    Expected(&'static str),
+    InvalidEscape,
+    InvalidUnicode(u32),
+    ControlCharacter,
+    LeadingZero,
+    MisplacedUnderscore,
+    IntegerOverflow,
+    InvalidDateTime,
+    DuplicateKey(I),
+    DuplicateTable(I),
+    InlineTableExtended(I),
+    DottedKeyClash(I),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Error<I> {
+    pub input: I,
+    pub kind: ErrorKind<I>,
+}
+
+impl<I> Error<I> {
+    pub fn new(input: I, kind: ErrorKind<I>) -> Self {
+        Self { input, kind }
+    }
+}
+
+impl<I> ParseError<I> for Error<I> {
+    fn from_error_kind(input: I, kind: NomErrorKind) -> Self {
+        Self::new(input, ErrorKind::Nom(kind))
+    }
+
+    fn append(_: I, _: NomErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I> ContextError<I> for Error<I> {
+    // keeps the innermost context and the errors of our own checks
+    fn add_context(input: I, context: &'static str, other: Self) -> Self {
+        match other.kind {
+            ErrorKind::Nom(_) => Self::new(input, ErrorKind::Expected(context)),
+            _ => other,
+        }
+    }
+}
+
+impl Display for Error<&str> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.kind {
+            ErrorKind::Nom(kind) => write!(f, "unexpected input ({})", kind.description()),
+            ErrorKind::Expected(expected) => write!(f, "expected {expected}"),
+            ErrorKind::InvalidEscape => {
+                let escape: String = self.input.chars().take(2).collect();
+                write!(f, "invalid escape sequence `{escape}`")
+            }
+            ErrorKind::InvalidUnicode(code) => {
+                write!(f, "invalid unicode scalar value U+{code:04X}")
+            }
+            ErrorKind::ControlCharacter => {
+                let code = self.input.chars().next().map_or(0, u32::from);
+                write!(f, "unescaped control character U+{code:04X}")
+            }
+            ErrorKind::LeadingZero => write!(f, "leading zeros are not allowed"),
+            ErrorKind::MisplacedUnderscore => write!(f, "underscores must be between digits"),
+            ErrorKind::IntegerOverflow => write!(f, "integer overflow"),
+            ErrorKind::InvalidDateTime => write!(f, "invalid date or time"),
+            ErrorKind::DuplicateKey(_) => write!(f, "duplicate key"),
+            ErrorKind::DuplicateTable(_) => write!(f, "duplicate table"),
+            ErrorKind::InlineTableExtended(_) => write!(f, "inline tables can't be extended"),
+            ErrorKind::DottedKeyClash(_) => {
+                write!(f, "dotted keys and headers can't define the same table")
+            }
+        }
+    }
+}
+
+impl Error<&str> {
+    // semantic errors span the whole definition, the others point at where
+    // parsing failed
+    pub fn into_diagnostic(self, source: &str) -> Diagnostic {
+        let span = |input: &str, len| source.offset(input)..source.offset(input) + len;
+        let first = match self.kind {
+            ErrorKind::DuplicateKey(first)
+            | ErrorKind::DuplicateTable(first)
+            | ErrorKind::InlineTableExtended(first)
+            | ErrorKind::DottedKeyClash(first) => Some(first),
+            _ => None,
+        };
+
+        match first {
+            Some(first) => {
+                let kind = DiagnosticKind::Semantic;
+                let note = span(first, first.len());
+                let note = Diagnostic::new(source, note, kind, "first defined here");
+                let span = span(self.input, self.input.len());
+                Diagnostic::new(source, span, kind, self.to_string()).with_note(note)
+            }
+            None => {
+                let kind = DiagnosticKind::Syntax;
+                let len = self.input.chars().next().map_or(0, char::len_utf8);
+                Diagnostic::new(source, span(self.input, len), kind, self.to_string())
+            }
+        }
+    }
+}
+
+type IResult<I, O> = nom::IResult<I, O, Error<I>>;
+
+fn parse_comment(s: &str) -> IResult<&str, &str> {
+    preceded(char('#'), not_line_ending).parse(s)
+}
+
+// skips whitespace, newlines and comments
+fn parse_ws_comment(s: &str) -> IResult<&str, ()> {
+    fold_many0(alt((multispace1, parse_comment)), || (), |_, _| ()).parse(s)
+}
+
 fn parse_float(s: &str) -> IResult<&str, f64> {
-    double(s)
+    let is_digit = |c: char| c.is_ascii_digit();
+    let exponent = || {
+        recognize(tuple((
+            one_of("eE"),
+            opt(one_of("+-")),
+            parse_digits(is_digit),
+        )))
+    };
+    let fraction = recognize(tuple((char('.'), parse_digits(is_digit), opt(exponent()))));
+    let decimal = recognize(tuple((
+        opt(one_of("+-")),
+        parse_digits(is_digit),
+        alt((fraction, exponent())),
+    )));
+    let special = recognize(pair(opt(one_of("+-")), alt((tag("inf"), tag("nan")))));
+    // leftovers of a malformed fraction or exponent like `1.5e`
+    let malformed = one_of(".eE");
+    let (rest, float) = terminated(alt((special, decimal)), not(malformed)).parse(s)?;
+
+    let failure = |kind| Err(NomErr::Failure(Error::new(s, kind)));
+    if rest.starts_with('_') {
+        return failure(ErrorKind::MisplacedUnderscore);
+    }
+    let integer_part = float.split(['.', 'e', 'E']).next().unwrap();
+    let unsigned = integer_part.trim_start_matches(['+', '-']);
+    if unsigned.len() > 1 && unsigned.starts_with('0') {
+        return failure(ErrorKind::LeadingZero);
+    }
+    // the grammar above only lets through what `f64::from_str` accepts
+    Ok((rest, float.replace('_', "").parse().unwrap()))
 }
 
 fn parse_boolean(s: &str) -> IResult<&str, bool> {
@@ -25,59 +187,369 @@ fn parse_boolean(s: &str) -> IResult<&str, bool> {
         .parse(s)
 }
 
+// digits with underscores only between them, like `1_000`
+fn parse_digits<'a>(
+    is_digit: fn(char) -> bool,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    recognize(separated_list1(char('_'), take_while1(is_digit)))
+}
+
 fn parse_integer(s: &str) -> IResult<&str, i64> {
-    let integer = map_res(digit1, str::parse);
-    let not_float = not(tuple((digit1, char('.'), digit1)));
-    not_float.and(integer).map(|(_, i)| i).parse(s)
+    let hex = preceded(tag("0x"), cut(parse_digits(|c| c.is_ascii_hexdigit())));
+    let oct = preceded(tag("0o"), cut(parse_digits(|c| matches!(c, '0'..='7'))));
+    let bin = preceded(tag("0b"), cut(parse_digits(|c| matches!(c, '0' | '1'))));
+    let dec = recognize(pair(
+        opt(one_of("+-")),
+        parse_digits(|c| c.is_ascii_digit()),
+    ));
+    // a float continues where its integer part ends
+    let not_float = not(one_of(".eE"));
+    let (rest, (radix, digits)) = terminated(
+        alt((
+            hex.map(|d| (16, d)),
+            oct.map(|d| (8, d)),
+            bin.map(|d| (2, d)),
+            dec.map(|d| (10, d)),
+        )),
+        not_float,
+    )
+    .parse(s)?;
+
+    let failure = |kind| Err(NomErr::Failure(Error::new(s, kind)));
+    if rest.starts_with('_') {
+        return failure(ErrorKind::MisplacedUnderscore);
+    }
+    let unsigned = digits.trim_start_matches(['+', '-']);
+    if radix == 10 && unsigned.len() > 1 && unsigned.starts_with('0') {
+        return failure(ErrorKind::LeadingZero);
+    }
+    match i64::from_str_radix(&digits.replace('_', ""), radix) {
+        Ok(i) => Ok((rest, i)),
+        Err(_) => failure(ErrorKind::IntegerOverflow),
+    }
 }
 
-fn parse_string(s: &str) -> IResult<&str, String> {
-    delimited(char('"'), take_till(|c| c == '"'), char('"'))
-        .map(|s: &str| s.to_string())
-        .parse(s)
+// exactly `n` digits, like the `MM` in `YYYY-MM-DD`
+fn parse_fixed_digits<'a, T: FromStr>(n: usize) -> impl FnMut(&'a str) -> IResult<&'a str, T> {
+    let par = take_while_m_n(n, n, |c: char| c.is_ascii_digit());
+    map_opt(par, |s: &str| s.parse().ok())
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    let is_leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if is_leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_date(s: &str) -> IResult<&str, Date> {
+    let (rest, (year, _, month, _, day)) = tuple((
+        parse_fixed_digits(4),
+        char('-'),
+        parse_fixed_digits(2),
+        char('-'),
+        parse_fixed_digits(2),
+    ))
+    .parse(s)?;
+
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(NomErr::Failure(Error::new(s, ErrorKind::InvalidDateTime)));
+    }
+    Ok((rest, Date { year, month, day }))
+}
+
+fn parse_time(s: &str) -> IResult<&str, Time> {
+    let (rest, (hour, _, minute, _, second, fraction)) = tuple((
+        parse_fixed_digits(2),
+        char(':'),
+        parse_fixed_digits(2),
+        char(':'),
+        parse_fixed_digits(2),
+        opt(preceded(
+            char('.'),
+            take_while1(|c: char| c.is_ascii_digit()),
+        )),
+    ))
+    .parse(s)?;
+
+    // a leap second is allowed
+    if hour > 23 || minute > 59 || second > 60 {
+        return Err(NomErr::Failure(Error::new(s, ErrorKind::InvalidDateTime)));
+    }
+    // digits past nanoseconds are truncated
+    let nanosecond = fraction.map_or(0, |f: &str| {
+        let f = &f[..f.len().min(9)];
+        f.parse::<u32>().unwrap() * 10u32.pow(9 - f.len() as u32)
+    });
+    let time = Time {
+        hour,
+        minute,
+        second,
+        nanosecond,
+    };
+    Ok((rest, time))
+}
+
+fn parse_offset(s: &str) -> IResult<&str, Offset> {
+    let numeric = tuple((
+        one_of("+-"),
+        parse_fixed_digits::<i16>(2),
+        char(':'),
+        parse_fixed_digits::<i16>(2),
+    ));
+    let (rest, numeric) = alt((value(None, one_of("Zz")), numeric.map(Some))).parse(s)?;
+
+    let Some((sign, hours, _, minutes)) = numeric else {
+        return Ok((rest, Offset::Z));
+    };
+    if hours > 23 || minutes > 59 {
+        return Err(NomErr::Failure(Error::new(s, ErrorKind::InvalidDateTime)));
+    }
+    let minutes = hours * 60 + minutes;
+    let minutes = if sign == '-' { -minutes } else { minutes };
+    Ok((rest, Offset::Minutes(minutes)))
+}
+
+// RFC 3339 date-times, allowing a space in place of the `T`
+fn parse_datetime(s: &str) -> IResult<&str, Value> {
+    let datetime = tuple((parse_date, one_of("Tt "), parse_time, opt(parse_offset)));
+    alt((
+        datetime.map(|(date, _, time, offset)| match offset {
+            Some(offset) => Value::OffsetDateTime(date, time, offset),
+            None => Value::LocalDateTime(date, time),
+        }),
+        parse_date.map(Value::LocalDate),
+        parse_time.map(Value::LocalTime),
+    ))
+    .parse(s)
 }
 
 fn parse_array(s: &str) -> IResult<&str, Array> {
-    let sep = tuple((multispace0, char(','), multispace0));
-    let par = separated_list0(sep, parse_value);
+    let sep = || tuple((parse_ws_comment, char(','), parse_ws_comment));
+    // a trailing comma is allowed after at least one value
+    let values = terminated(separated_list1(sep(), parse_value), opt(sep()));
+    let par = opt(values).map(Option::unwrap_or_default);
+    let end = cut(context("`,` or `]`", char(']')));
     delimited(
-        pair(char('['), multispace0),
+        pair(char('['), parse_ws_comment),
         par,
-        pair(multispace0, char(']')),
+        preceded(parse_ws_comment, end),
     )
     .map(Array)
     .parse(s)
 }
 
+enum Fragment<'a> {
+    Str(&'a str),
+    Char(char),
+}
+
+// surrogates and code points past U+10FFFF aren't unicode scalar values
+fn parse_unicode<'a>(n: usize) -> impl FnMut(&'a str) -> IResult<&'a str, char> {
+    move |s: &'a str| {
+        let (rest, hex) = take_while_m_n(n, n, |c: char| c.is_ascii_hexdigit())(s)?;
+        let code = u32::from_str_radix(hex, 16).unwrap();
+        match char::from_u32(code) {
+            Some(c) => Ok((rest, c)),
+            None => Err(NomErr::Failure(Error::new(
+                s,
+                ErrorKind::InvalidUnicode(code),
+            ))),
+        }
+    }
+}
+
+fn parse_escape(s: &str) -> IResult<&str, char> {
+    let (rest, _) = char('\\')(s)?;
+    alt((
+        value('\u{08}', char('b')),
+        value('\t', char('t')),
+        value('\n', char('n')),
+        value('\u{0C}', char('f')),
+        value('\r', char('r')),
+        value('"', char('"')),
+        value('\\', char('\\')),
+        preceded(char('u'), parse_unicode(4)),
+        preceded(char('U'), parse_unicode(8)),
+    ))
+    .parse(rest)
+    .map_err(|err| match err {
+        NomErr::Error(_) => NomErr::Failure(Error::new(s, ErrorKind::InvalidEscape)),
+        err => err,
+    })
+}
+
+// control characters that can't appear unescaped in strings, tab is allowed
+// and line breaks either end a string or belong to a multi-line one
+fn is_control(c: char) -> bool {
+    matches!(c, '\0'..='\x08' | '\x0B'..='\x1F' | '\x7F')
+}
+
+// fails on a control character where a string goes on, so that the error
+// points at it instead of asking for the closing quotes
+fn parse_control<O>(s: &str) -> IResult<&str, O> {
+    match s.chars().next() {
+        Some(c) if is_control(c) => {
+            Err(NomErr::Failure(Error::new(s, ErrorKind::ControlCharacter)))
+        }
+        _ => Err(NomErr::Error(Error::from_error_kind(s, NomErrorKind::Char))),
+    }
+}
+
+fn push_fragment(mut string: String, fragment: Fragment) -> String {
+    match fragment {
+        Fragment::Str(s) => string.push_str(s),
+        Fragment::Char(c) => string.push(c),
+    }
+    string
+}
+
+fn parse_basic_string(s: &str) -> IResult<&str, String> {
+    let chars = take_while1(|c| !matches!(c, '"' | '\\' | '\n') && !is_control(c));
+    let fragment = alt((chars.map(Fragment::Str), parse_escape.map(Fragment::Char)));
+    let par = fold_many0(fragment, String::new, push_fragment);
+    let end = cut(context("a closing `\"`", alt((char('"'), parse_control))));
+    delimited(char('"'), par, end).parse(s)
+}
+
+fn parse_literal_string(s: &str) -> IResult<&str, String> {
+    delimited(
+        char('\''),
+        take_till(|c| c == '\'' || c == '\n' || is_control(c)),
+        cut(context("a closing `'`", alt((char('\''), parse_control)))),
+    )
+    .map(|s: &str| s.to_string())
+    .parse(s)
+}
+
+// takes one or two quotes that don't close a multi-line string, four or five
+// quotes in a row mean the string ends with one or two quotes
+fn parse_ml_quotes<'a>(quote: char) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    move |s: &'a str| {
+        let n = s.chars().take_while(|&c| c == quote).count();
+        match n {
+            1 | 2 => Ok((&s[n..], &s[..n])),
+            4 | 5 => Ok((&s[n - 3..], &s[..n - 3])),
+            _ => Err(NomErr::Error(Error::from_error_kind(s, NomErrorKind::Char))),
+        }
+    }
+}
+
+// a backslash at the end of a line trims all whitespace up to the next
+// non-whitespace character
+fn parse_line_ending_backslash(s: &str) -> IResult<&str, &str> {
+    let par = tuple((char('\\'), space0, line_ending, multispace0));
+    value("", par).parse(s)
+}
+
+fn parse_ml_basic_string(s: &str) -> IResult<&str, String> {
+    let chars = take_while1(|c| !matches!(c, '"' | '\\') && !is_control(c));
+    let fragment = alt((
+        chars.map(Fragment::Str),
+        tag("\r\n").map(Fragment::Str),
+        parse_ml_quotes('"').map(Fragment::Str),
+        parse_line_ending_backslash.map(Fragment::Str),
+        parse_escape.map(Fragment::Char),
+    ));
+    let par = fold_many0(fragment, String::new, push_fragment);
+    let end = cut(context(
+        "a closing `\"\"\"`",
+        alt((tag("\"\"\""), parse_control)),
+    ));
+    delimited(pair(tag("\"\"\""), opt(line_ending)), par, end).parse(s)
+}
+
+fn parse_ml_literal_string(s: &str) -> IResult<&str, String> {
+    let chars = take_while1(|c| c != '\'' && !is_control(c));
+    let fragment = alt((chars, tag("\r\n"), parse_ml_quotes('\'')));
+    let par = fold_many0(fragment, String::new, |string, s| string + s);
+    let end = cut(context("a closing `'''`", alt((tag("'''"), parse_control))));
+    delimited(pair(tag("'''"), opt(line_ending)), par, end).parse(s)
+}
+
+fn parse_string(s: &str) -> IResult<&str, String> {
+    alt((
+        parse_ml_basic_string,
+        parse_basic_string,
+        parse_ml_literal_string,
+        parse_literal_string,
+    ))
+    .parse(s)
+}
+
 fn parse_identifier(s: &str) -> IResult<&str, Identifier> {
     let par = alt((alphanumeric1, tag("-"), tag("_")));
-    fold_many1(par, String::new, |string, s| string + s)
+    let bare = fold_many1(par, String::new, |string, s| string + s);
+    alt((bare, parse_basic_string, parse_literal_string))
         .map(Identifier)
         .parse(s)
 }
 
-fn parse_pair(s: &str) -> IResult<&str, Pair> {
+fn parse_key(s: &str) -> IResult<&str, Vec<Identifier>> {
+    let sep = tuple((space0, char('.'), space0));
+    separated_list1(sep, parse_identifier).parse(s)
+}
+
+// a pair as written, with the dotted key not yet turned into tables
+type KeyValue = (Vec<Identifier>, Value);
+
+// nothing but a pair can start with a key, so a missing `=` or value is an error
+fn parse_pair(s: &str) -> IResult<&str, KeyValue> {
     let sep = tuple((space0, char('='), space0));
-    separated_pair(parse_identifier, sep, parse_value)
-        .map(|(key, value)| Pair { key, value })
-        .parse(s)
+    let sep = cut(context("`=`", sep));
+    let value = cut(context("a value", parse_value));
+    separated_pair(parse_key, sep, value).parse(s)
+}
+
+// a value with the input it was parsed from, which errors point to
+type Located<'a, O> = (&'a str, O);
+
+fn located<'a, O>(
+    mut par: impl Parser<&'a str, O, Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Located<'a, O>> {
+    move |s: &'a str| {
+        let (rest, o) = par.parse(s)?;
+        Ok((rest, (&s[..s.offset(rest)], o)))
+    }
+}
+
+// turns a dotted key like `a.b.c = 1` into nested inline tables
+fn into_pair(mut keys: Vec<Identifier>, value: Value) -> Pair {
+    let key = keys.pop().unwrap();
+    let pair = Pair { key, value };
+    keys.into_iter().rev().fold(pair, |pair, key| {
+        let value = Value::InlineTable(InlineTable::from_iter([pair]));
+        Pair { key, value }
+    })
 }
 
 fn parse_inline_table(s: &str) -> IResult<&str, InlineTable> {
     let sep = tuple((multispace0, char(','), multispace0));
-    let par = separated_list0(sep, parse_pair);
-    delimited(
+    let par = separated_list0(sep, located(parse_pair));
+    let end = cut(context("`,` or `}`", char('}')));
+    let (rest, pairs) = delimited(
         pair(char('{'), multispace0),
         par,
-        pair(multispace0, char('}')),
+        preceded(multispace0, end),
     )
-    .map(InlineTable)
-    .parse(s)
+    .parse(s)?;
+
+    let mut validator = Validator::default();
+    let mut table = InlineTable::default();
+    for (at, (keys, value)) in pairs {
+        validator.pair(at, &keys, &value).map_err(NomErr::Failure)?;
+        table.insert(into_pair(keys, value));
+    }
+    Ok((rest, table))
 }
 
 fn parse_value(s: &str) -> IResult<&str, Value> {
     alt((
         parse_boolean.map(Value::Boolean),
+        parse_datetime,
         parse_integer.map(Value::Integer),
         parse_float.map(Value::Float),
         parse_string.map(Value::String),
@@ -87,47 +559,143 @@ fn parse_value(s: &str) -> IResult<&str, Value> {
     .parse(s)
 }
 
-fn parse_table_body(s: &str) -> IResult<&str, InlineTable> {
-    let par = tuple((multispace0, parse_pair, space0)).map(|(_, p, _)| p);
-    separated_list0(newline, par).map(InlineTable).parse(s)
+// the rest of a line after a pair or a header, which may only be a comment
+fn parse_line_end(s: &str) -> IResult<&str, ()> {
+    let end = peek(alt((line_ending, eof)));
+    let par = tuple((space0, opt(parse_comment), cut(context("a new line", end))));
+    value((), par).parse(s)
 }
 
-fn parse_table(s: &str) -> IResult<&str, Table> {
-    let header = tuple((
-        multispace0,
-        char('['),
-        space0,
-        parse_identifier,
-        space0,
-        char(']'),
-        space0,
-        newline,
-    ))
-    .map(|(_, _, _, i, _, _, _, _)| i);
+fn parse_header(s: &str) -> IResult<&str, Header> {
+    let key = || cut(context("a key", parse_key));
+    let array = delimited(
+        pair(tag("[["), space0),
+        key(),
+        preceded(space0, cut(context("`]]`", tag("]]")))),
+    );
+    let table = delimited(
+        pair(char('['), space0),
+        key(),
+        preceded(space0, cut(context("`]`", char(']')))),
+    );
+    alt((array.map(Header::ArrayOfTables), table.map(Header::Table))).parse(s)
+}
 
-    pair(header, parse_table_body)
-        .map(|(header, body)| Table { header, body })
+#[derive(Debug, PartialEq)]
+enum Statement {
+    Header(Header),
+    Pair(KeyValue),
+}
+
+fn parse_statement(s: &str) -> IResult<&str, Located<'_, Statement>> {
+    let par = alt((
+        parse_header.map(Statement::Header),
+        parse_pair.map(Statement::Pair),
+    ));
+    let par = context("a key or a table header", par);
+    terminated(located(par), parse_line_end).parse(s)
+}
+
+// after an error, lines are skipped until one that starts like a statement,
+// so that the rest of a broken multi-line value isn't reported line by line
+fn is_statement_start(s: &str) -> bool {
+    let pair = tuple((parse_key, space0, char('=')));
+    alt((value((), char('[')), value((), pair)))
         .parse(s)
+        .is_ok()
 }
 
-fn parse_document(s: &str) -> IResult<&str, Document> {
-    let par = fold_many0(parse_table, Vec::new, |mut vec, t| {
-        vec.push(t);
-        vec
-    });
-    tuple((opt(parse_table_body), par, multispace0, eof))
-        .map(|(opt, mut vec, _, _)| {
-            if let Some(body) = opt {
-                let header = Identifier(String::new());
-                vec.insert(0, Table { header, body });
+fn skip_line(s: &str) -> &str {
+    &s[s.find('\n').map_or(s.len(), |i| i + 1)..]
+}
+
+// parses statement by statement and skips to the next line on errors, so that
+// every error of the document is found in one go
+fn parse_document(s: &str) -> (Document, Vec<Error<&str>>) {
+    let mut validator = Validator::default();
+    let mut root = InlineTable::default();
+    let mut tables: Vec<Table> = Vec::new();
+    let mut errors = Vec::new();
+    let mut recovering = false;
+    // the pairs under a broken header belong to no table
+    let mut skip_section = false;
+
+    let mut input = s;
+    loop {
+        let (rest, _) = parse_ws_comment(input).expect("skipping whitespace never fails");
+        if rest.is_empty() {
+            break;
+        }
+        if recovering && !is_statement_start(rest) {
+            input = skip_line(rest);
+            continue;
+        }
+        recovering = false;
+
+        let (at, statement) = match parse_statement(rest) {
+            Ok((rest, statement)) => {
+                input = rest;
+                statement
             }
-            Document(vec)
-        })
+            Err(NomErr::Error(err) | NomErr::Failure(err)) => {
+                skip_section |= rest.starts_with('[');
+                // errors of inline tables point inside the line
+                input = skip_line(&s[s.offset(err.input)..]);
+                recovering = true;
+                errors.push(err);
+                continue;
+            }
+            Err(NomErr::Incomplete(_)) => unreachable!("parsers are complete"),
+        };
+
+        match statement {
+            Statement::Header(header) => match validator.header(at, &header) {
+                Ok(()) => {
+                    skip_section = false;
+                    let body = InlineTable::default();
+                    tables.push(Table { header, body });
+                }
+                Err(err) => {
+                    skip_section = true;
+                    errors.push(err);
+                }
+            },
+            Statement::Pair(_) if skip_section => {}
+            Statement::Pair((keys, value)) => match validator.pair(at, &keys, &value) {
+                Ok(()) => {
+                    let table = tables.last_mut().map_or(&mut root, |t| &mut t.body);
+                    table.insert(into_pair(keys, value));
+                }
+                Err(err) => errors.push(err),
+            },
+        }
+    }
+
+    let mut doc = Document(root);
+    tables.into_iter().for_each(|t| doc.insert(t));
+    (doc, errors)
+}
+
+// parses a dotted key on its own, like the ones given on the command line
+pub fn parse_query(s: &str) -> Option<Vec<Identifier>> {
+    delimited(space0, parse_key, pair(space0, eof))
         .parse(s)
+        .ok()
+        .map(|(_, keys)| keys)
+}
+
+// returns what could be parsed of the document along with every error
+pub fn parse_partial(s: &str) -> (Document, Vec<Diagnostic>) {
+    let (doc, errors) = parse_document(s);
+    let diagnostics = errors.into_iter().map(|e| e.into_diagnostic(s)).collect();
+    (doc, diagnostics)
 }
 
-pub fn parse(s: &str) -> Result<Document, NomError<&str>> {
-    parse_document(s).finish().map(|(_, vec)| vec)
+pub fn parse(s: &str) -> Result<Document, Vec<Diagnostic>> {
+    match parse_partial(s) {
+        (doc, diagnostics) if diagnostics.is_empty() => Ok(doc),
+        (_, diagnostics) => Err(diagnostics),
+    }
 }
 
 #[cfg(test)]
@@ -135,6 +703,18 @@ pub mod test {
     use super::*;
     use insta::{assert_compact_debug_snapshot, assert_debug_snapshot};
 
+    #[test]
+    fn test_parse_comment_1() {
+        let r = parse_comment("# abc\nother").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("\nother", " abc")"#)
+    }
+
+    #[test]
+    fn test_parse_ws_comment_1() {
+        let r = parse_ws_comment("  # abc\n\n\t# def\n other").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("other", ())"#)
+    }
+
     #[test]
     fn test_parse_boolean_1() {
         let r = parse_boolean("false").unwrap();
@@ -164,6 +744,79 @@ pub mod test {
         assert_compact_debug_snapshot!(r, @r#"("other", 1)"#)
     }
 
+    #[test]
+    fn test_parse_integer_3() {
+        let r = [
+            "-5",
+            "+3",
+            "-0",
+            "1_000_000",
+            "0xDEAD_BEEF",
+            "0o755",
+            "0b1010",
+        ]
+        .map(|s| parse_integer(s).unwrap().1);
+        assert_compact_debug_snapshot!(r, @"[-5, 3, 0, 1000000, 3735928559, 493, 10]")
+    }
+
+    #[test]
+    fn test_parse_integer_4() {
+        let r = parse_integer("-9223372036854775808").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", -9223372036854775808)"#)
+    }
+
+    #[test]
+    fn test_parse_integer_5() {
+        let r = parse_integer("9223372036854775808").unwrap_err();
+        assert_compact_debug_snapshot!(r, @r#"Failure(Error { input: "9223372036854775808", kind: IntegerOverflow })"#)
+    }
+
+    #[test]
+    fn test_parse_integer_6() {
+        let r = ["0123", "1__000", "1_", "0x1F_", "0x_1F"].map(|s| parse_integer(s).unwrap_err());
+        assert_debug_snapshot!(r, @r#"
+        [
+            Failure(
+                Error {
+                    input: "0123",
+                    kind: LeadingZero,
+                },
+            ),
+            Failure(
+                Error {
+                    input: "1__000",
+                    kind: MisplacedUnderscore,
+                },
+            ),
+            Failure(
+                Error {
+                    input: "1_",
+                    kind: MisplacedUnderscore,
+                },
+            ),
+            Failure(
+                Error {
+                    input: "0x1F_",
+                    kind: MisplacedUnderscore,
+                },
+            ),
+            Failure(
+                Error {
+                    input: "_1F",
+                    kind: Nom(
+                        TakeWhile1,
+                    ),
+                },
+            ),
+        ]
+        "#)
+    }
+
+    #[test]
+    fn test_parse_integer_7() {
+        let _ = parse_integer("3.14").unwrap_err();
+    }
+
     #[test]
     fn test_parse_float_1() {
         let r = parse_float("1.0").unwrap();
@@ -182,6 +835,128 @@ pub mod test {
         assert_compact_debug_snapshot!(r, @r#"(" remaining", 0.1)"#)
     }
 
+    #[test]
+    fn test_parse_float_4() {
+        let r = [
+            "+1.0",
+            "-0.01",
+            "5e+22",
+            "1e06",
+            "-2E-2",
+            "6.626e-34",
+            "1_000.5",
+            "9_224.617_445",
+            "-0.0",
+        ]
+        .map(|s| parse_float(s).unwrap().1);
+        assert_compact_debug_snapshot!(r, @"[1.0, -0.01, 5e22, 1000000.0, -0.02, 6.626e-34, 1000.5, 9224.617445, -0.0]")
+    }
+
+    #[test]
+    fn test_parse_float_5() {
+        let r = ["inf", "+inf", "-inf", "nan", "+nan"].map(|s| parse_float(s).unwrap().1);
+        assert_compact_debug_snapshot!(r, @"[inf, inf, -inf, NaN, NaN]")
+    }
+
+    #[test]
+    fn test_parse_float_6() {
+        let r = [".5", "5.", "1e", "1.e5", "1.5e"].map(|s| parse_float(s).is_ok());
+        assert_compact_debug_snapshot!(r, @"[false, false, false, false, false]")
+    }
+
+    #[test]
+    fn test_parse_float_7() {
+        let r = ["03.14", "1__0.5", "1.5_"].map(|s| parse_float(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[Failure(Error { input: "03.14", kind: LeadingZero }), Error(Error { input: "__0.5", kind: Nom(OneOf) }), Failure(Error { input: "1.5_", kind: MisplacedUnderscore })]"#)
+    }
+
+    #[test]
+    fn test_parse_datetime_1() {
+        let r = parse_datetime("1979-05-27T07:32:00.123456789123-07:30").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", OffsetDateTime(Date { year: 1979, month: 5, day: 27 }, Time { hour: 7, minute: 32, second: 0, nanosecond: 123456789 }, Minutes(-450)))"#)
+    }
+
+    #[test]
+    fn test_parse_datetime_2() {
+        let r = [
+            "1979-05-27 07:32:00z",
+            "1979-05-27t07:32:00",
+            "2000-02-29 ",
+            "07:32:00",
+        ]
+        .map(|s| parse_datetime(s).unwrap());
+        assert_debug_snapshot!(r, @r#"
+        [
+            (
+                "",
+                OffsetDateTime(
+                    Date {
+                        year: 1979,
+                        month: 5,
+                        day: 27,
+                    },
+                    Time {
+                        hour: 7,
+                        minute: 32,
+                        second: 0,
+                        nanosecond: 0,
+                    },
+                    Z,
+                ),
+            ),
+            (
+                "",
+                LocalDateTime(
+                    Date {
+                        year: 1979,
+                        month: 5,
+                        day: 27,
+                    },
+                    Time {
+                        hour: 7,
+                        minute: 32,
+                        second: 0,
+                        nanosecond: 0,
+                    },
+                ),
+            ),
+            (
+                " ",
+                LocalDate(
+                    Date {
+                        year: 2000,
+                        month: 2,
+                        day: 29,
+                    },
+                ),
+            ),
+            (
+                "",
+                LocalTime(
+                    Time {
+                        hour: 7,
+                        minute: 32,
+                        second: 0,
+                        nanosecond: 0,
+                    },
+                ),
+            ),
+        ]
+        "#)
+    }
+
+    #[test]
+    fn test_parse_datetime_3() {
+        let r = [
+            "2023-02-29",
+            "1979-13-01",
+            "24:00:00",
+            "1979-05-27T07:32:00+24:00",
+        ]
+        .map(|s| parse_datetime(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[Failure(Error { input: "2023-02-29", kind: InvalidDateTime }), Failure(Error { input: "1979-13-01", kind: InvalidDateTime }), Failure(Error { input: "24:00:00", kind: InvalidDateTime }), Failure(Error { input: "+24:00", kind: InvalidDateTime })]"#)
+    }
+
     #[test]
     fn test_parse_string_1() {
         let r = parse_string("\"abc\"").unwrap();
@@ -194,6 +969,80 @@ pub mod test {
         assert_compact_debug_snapshot!(r, @r#"("other", "abc")"#)
     }
 
+    #[test]
+    fn test_parse_string_3() {
+        let r = parse_string(r#"'C:\Users\"nodejs"'"#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "C:\\Users\\\"nodejs\"")"#)
+    }
+
+    #[test]
+    fn test_parse_string_4() {
+        let r = parse_string("\"\"\"\nRoses are red\r\nViolets are blue\"\"\"").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "Roses are red\r\nViolets are blue")"#)
+    }
+
+    #[test]
+    fn test_parse_string_5() {
+        let r = parse_string("\"\"\"The quick \\\n\n   brown \\  \n  fox.\"\"\"").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "The quick brown fox.")"#)
+    }
+
+    #[test]
+    fn test_parse_string_6() {
+        let r = parse_string(r#""""Here are two quotes: "". Ends with one.""""other"#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("other", "Here are two quotes: \"\". Ends with one.\"")"#)
+    }
+
+    #[test]
+    fn test_parse_string_7() {
+        let r =
+            parse_string("'''\nThe first newline is\ntrimmed in 'raw' strings.\n'''''").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "The first newline is\ntrimmed in 'raw' strings.\n''")"#)
+    }
+
+    #[test]
+    fn test_parse_string_8() {
+        let r = parse_string(r#""""""""#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "")"#)
+    }
+
+    #[test]
+    fn test_parse_string_9() {
+        let r = parse_string(r#""\b\t\n\f\r\"\\ é \U0001F600""#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", "\u{8}\t\n\u{c}\r\"\\ é 😀")"#)
+    }
+
+    #[test]
+    fn test_parse_string_10() {
+        let r = parse_string(r#""a \q b""#).unwrap_err();
+        assert_compact_debug_snapshot!(r, @r#"Failure(Error { input: "\\q b\"", kind: InvalidEscape })"#)
+    }
+
+    #[test]
+    fn test_parse_string_11() {
+        let r = parse_string(r#""""a \uD800 b""""#).unwrap_err();
+        assert_compact_debug_snapshot!(r, @r#"Failure(Error { input: "D800 b\"\"\"", kind: InvalidUnicode(55296) })"#)
+    }
+
+    #[test]
+    fn test_parse_string_12() {
+        let r = parse_string(r#""\u12 short""#).unwrap_err();
+        assert_compact_debug_snapshot!(r, @r#"Failure(Error { input: "\\u12 short\"", kind: InvalidEscape })"#)
+    }
+
+    #[test]
+    fn test_parse_string_13() {
+        let r = [
+            "\"a\u{1}b\"",
+            "'a\u{7f}b'",
+            "\"\"\"a\rb\"\"\"",
+            "'''a\r\n\u{0}'''",
+            "\"a\tb\"",
+        ]
+        .map(parse_string);
+        assert_compact_debug_snapshot!(r, @r#"[Err(Failure(Error { input: "\u{1}b\"", kind: ControlCharacter })), Err(Failure(Error { input: "\u{7f}b'", kind: ControlCharacter })), Err(Failure(Error { input: "\rb\"\"\"", kind: ControlCharacter })), Err(Failure(Error { input: "\0'''", kind: ControlCharacter })), Ok(("", "a\tb"))]"#)
+    }
+
     #[test]
     fn test_parse_array_1() {
         let r = parse_array("[1,2]").unwrap();
@@ -226,6 +1075,18 @@ pub mod test {
         "#)
     }
 
+    #[test]
+    fn test_parse_array_3() {
+        let r = parse_array("[ # first\n  1, # one\n  # two\n  2\n]").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", Array([Integer(1), Integer(2)]))"#)
+    }
+
+    #[test]
+    fn test_parse_array_4() {
+        let r = ["[ 1, 2, ]", "[ 1, 2 3 ]", "[,]"].map(parse_array);
+        assert_compact_debug_snapshot!(r, @r#"[Ok(("", Array([Integer(1), Integer(2)]))), Err(Failure(Error { input: "3 ]", kind: Expected("`,` or `]`") })), Err(Failure(Error { input: ",]", kind: Expected("`,` or `]`") }))]"#)
+    }
+
     #[test]
     fn test_parse_identifier_1() {
         let r = parse_identifier("abc").unwrap();
@@ -238,20 +1099,52 @@ pub mod test {
         assert_compact_debug_snapshot!(r, @r#"("", Identifier("-ab_c"))"#)
     }
 
+    #[test]
+    fn test_parse_identifier_3() {
+        let r = parse_identifier(r#""127.0.0.1""#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", Identifier("127.0.0.1"))"#)
+    }
+
+    #[test]
+    fn test_parse_identifier_4() {
+        let r = parse_identifier("'key with spaces'").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", Identifier("key with spaces"))"#)
+    }
+
+    #[test]
+    fn test_parse_identifier_5() {
+        let r = parse_identifier(r#""tab\t quote\" é \U0001F600""#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", Identifier("tab\t quote\" é 😀"))"#)
+    }
+
+    #[test]
+    fn test_parse_identifier_6() {
+        let r = parse_identifier(r#""" = 1"#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"(" = 1", Identifier(""))"#)
+    }
+
+    #[test]
+    fn test_parse_key_1() {
+        let r = parse_key("a.b . c = 1").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"(" = 1", [Identifier("a"), Identifier("b"), Identifier("c")])"#)
+    }
+
     #[test]
     fn test_parse_pair_1() {
         let r = parse_pair("abc=\"def\"").unwrap();
         assert_debug_snapshot!(r, @r#"
         (
             "",
-            Pair {
-                key: Identifier(
-                    "abc",
-                ),
-                value: String(
+            (
+                [
+                    Identifier(
+                        "abc",
+                    ),
+                ],
+                String(
                     "def",
                 ),
-            },
+            ),
         )
         "#)
     }
@@ -262,18 +1155,32 @@ pub mod test {
         assert_debug_snapshot!(r, @r#"
         (
             "",
-            Pair {
-                key: Identifier(
-                    "abc",
-                ),
-                value: String(
+            (
+                [
+                    Identifier(
+                        "abc",
+                    ),
+                ],
+                String(
                     "def",
                 ),
-            },
+            ),
         )
         "#)
     }
 
+    #[test]
+    fn test_parse_key_2() {
+        let r = parse_key(r#"site."google.com".'x y'"#).unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", [Identifier("site"), Identifier("google.com"), Identifier("x y")])"#)
+    }
+
+    #[test]
+    fn test_parse_pair_3() {
+        let r = parse_pair("a.b.c = 1").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", ([Identifier("a"), Identifier("b"), Identifier("c")], Integer(1)))"#)
+    }
+
     #[test]
     fn test_parse_inline_table_1() {
         let r = parse_inline_table("{ abc = \"def\" }").unwrap();
@@ -296,6 +1203,70 @@ pub mod test {
         "#)
     }
 
+    #[test]
+    fn test_parse_inline_table_2() {
+        let r = parse_inline_table("{ a.b = 1, a.c = 2, d = 3 }").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", InlineTable([Pair { key: Identifier("a"), value: InlineTable(InlineTable([Pair { key: Identifier("b"), value: Integer(1) }, Pair { key: Identifier("c"), value: Integer(2) }])) }, Pair { key: Identifier("d"), value: Integer(3) }]))"#)
+    }
+
+    #[test]
+    fn test_parse_header_1() {
+        let r = parse_header("[[ fruit.variety ]]").unwrap();
+        assert_compact_debug_snapshot!(r, @r#"("", ArrayOfTables([Identifier("fruit"), Identifier("variety")]))"#)
+    }
+
+    #[test]
+    fn test_parse_statement_1() {
+        let r =
+            ["[ a . \"b.c\" .'d' ] # comment\n", "a.b = 1"].map(|s| parse_statement(s).unwrap());
+        assert_compact_debug_snapshot!(r, @r#"[("\n", ("[ a . \"b.c\" .'d' ]", Header(Table([Identifier("a"), Identifier("b.c"), Identifier("d")])))), ("", ("a.b = 1", Pair(([Identifier("a"), Identifier("b")], Integer(1)))))]"#)
+    }
+
+    #[test]
+    fn test_parse_document_tables() {
+        let s = "x.y = 1\n[servers.alpha]\nip = 1\n[x.z]\n[servers.beta]\nip = 2\n";
+        let r = parse_document(s);
+        assert_compact_debug_snapshot!(r, @r#"(Document(InlineTable([Pair { key: Identifier("x"), value: InlineTable(InlineTable([Pair { key: Identifier("y"), value: Integer(1) }, Pair { key: Identifier("z"), value: InlineTable(InlineTable([])) }])) }, Pair { key: Identifier("servers"), value: InlineTable(InlineTable([Pair { key: Identifier("alpha"), value: InlineTable(InlineTable([Pair { key: Identifier("ip"), value: Integer(1) }])) }, Pair { key: Identifier("beta"), value: InlineTable(InlineTable([Pair { key: Identifier("ip"), value: Integer(2) }])) }])) }])), [])"#)
+    }
+
+    #[test]
+    fn test_parse_document_array_of_tables() {
+        let s = "[[a]]\nx = 1\n[a.b]\ny = 2\n[[a]]\n[[a.c]]\nz = 3\n";
+        let r = parse_document(s);
+        assert_compact_debug_snapshot!(r, @r#"(Document(InlineTable([Pair { key: Identifier("a"), value: Array(Array([InlineTable(InlineTable([Pair { key: Identifier("x"), value: Integer(1) }, Pair { key: Identifier("b"), value: InlineTable(InlineTable([Pair { key: Identifier("y"), value: Integer(2) }])) }])), InlineTable(InlineTable([Pair { key: Identifier("c"), value: Array(Array([InlineTable(InlineTable([Pair { key: Identifier("z"), value: Integer(3) }]))])) }]))])) }])), [])"#)
+    }
+
+    #[test]
+    fn test_parse_document_comments() {
+        let s = "# header comment\na = 1 # trailing\n\n# table comment\n[b] # header\nc = 2\n# end";
+        let r = parse_document(s);
+        assert_compact_debug_snapshot!(r, @r#"(Document(InlineTable([Pair { key: Identifier("a"), value: Integer(1) }, Pair { key: Identifier("b"), value: InlineTable(InlineTable([Pair { key: Identifier("c"), value: Integer(2) }])) }])), [])"#)
+    }
+
+    #[test]
+    fn test_parse_document_recovery() {
+        let s = r#"
+a = 1
+b = [
+  1,
+  2 3,
+]
+c = "ok"
+d = 1 = 2
+
+[t
+x = 1
+
+[u]
+a = 1
+a = { y = 2, y = 3 }
+z = true
+"#;
+        let (doc, errors) = parse_document(s);
+        let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_compact_debug_snapshot!((doc, errors), @r#"(Document(InlineTable([Pair { key: Identifier("a"), value: Integer(1) }, Pair { key: Identifier("c"), value: String("ok") }, Pair { key: Identifier("u"), value: InlineTable(InlineTable([Pair { key: Identifier("a"), value: Integer(1) }, Pair { key: Identifier("z"), value: Boolean(true) }])) }])), ["expected `,` or `]`", "expected a new line", "expected `]`", "duplicate key"])"#)
+    }
+
     pub const TOML: &str = r#"
 title = "TOML Example"
 
@@ -318,106 +1289,148 @@ role = "backend""#;
 
     #[test]
     fn test_parse_document() {
-        let r = parse_document(TOML).unwrap();
+        let r = parse_document(TOML);
         assert_debug_snapshot!(r, @r#"
         (
-            "",
             Document(
-                [
-                    Table {
-                        header: Identifier(
-                            "",
-                        ),
-                        body: InlineTable(
-                            [
-                                Pair {
-                                    key: Identifier(
-                                        "title",
-                                    ),
-                                    value: String(
-                                        "TOML Example",
-                                    ),
-                                },
-                            ],
-                        ),
-                    },
-                    Table {
-                        header: Identifier(
-                            "owner",
-                        ),
-                        body: InlineTable(
-                            [
-                                Pair {
-                                    key: Identifier(
-                                        "name",
-                                    ),
-                                    value: String(
-                                        "Tom Preston-Werner",
-                                    ),
-                                },
-                            ],
-                        ),
-                    },
-                    Table {
-                        header: Identifier(
-                            "database",
-                        ),
-                        body: InlineTable(
-                            [
-                                Pair {
-                                    key: Identifier(
-                                        "enabled",
-                                    ),
-                                    value: Boolean(
-                                        true,
-                                    ),
-                                },
-                                Pair {
-                                    key: Identifier(
-                                        "ports",
-                                    ),
-                                    value: Array(
-                                        Array(
-                                            [
-                                                Integer(
-                                                    8000,
-                                                ),
-                                                Integer(
-                                                    8001,
-                                                ),
-                                                Integer(
-                                                    8002,
+                InlineTable(
+                    [
+                        Pair {
+                            key: Identifier(
+                                "title",
+                            ),
+                            value: String(
+                                "TOML Example",
+                            ),
+                        },
+                        Pair {
+                            key: Identifier(
+                                "owner",
+                            ),
+                            value: InlineTable(
+                                InlineTable(
+                                    [
+                                        Pair {
+                                            key: Identifier(
+                                                "name",
+                                            ),
+                                            value: String(
+                                                "Tom Preston-Werner",
+                                            ),
+                                        },
+                                    ],
+                                ),
+                            ),
+                        },
+                        Pair {
+                            key: Identifier(
+                                "database",
+                            ),
+                            value: InlineTable(
+                                InlineTable(
+                                    [
+                                        Pair {
+                                            key: Identifier(
+                                                "enabled",
+                                            ),
+                                            value: Boolean(
+                                                true,
+                                            ),
+                                        },
+                                        Pair {
+                                            key: Identifier(
+                                                "ports",
+                                            ),
+                                            value: Array(
+                                                Array(
+                                                    [
+                                                        Integer(
+                                                            8000,
+                                                        ),
+                                                        Integer(
+                                                            8001,
+                                                        ),
+                                                        Integer(
+                                                            8002,
+                                                        ),
+                                                    ],
                                                 ),
-                                            ],
-                                        ),
-                                    ),
-                                },
-                                Pair {
-                                    key: Identifier(
-                                        "data",
-                                    ),
-                                    value: Array(
-                                        Array(
-                                            [
+                                            ),
+                                        },
+                                        Pair {
+                                            key: Identifier(
+                                                "data",
+                                            ),
+                                            value: Array(
                                                 Array(
-                                                    Array(
-                                                        [
-                                                            String(
-                                                                "delta",
+                                                    [
+                                                        Array(
+                                                            Array(
+                                                                [
+                                                                    String(
+                                                                        "delta",
+                                                                    ),
+                                                                    String(
+                                                                        "phi",
+                                                                    ),
+                                                                ],
                                                             ),
-                                                            String(
-                                                                "phi",
+                                                        ),
+                                                        Array(
+                                                            Array(
+                                                                [
+                                                                    Float(
+                                                                        3.14,
+                                                                    ),
+                                                                    InlineTable(
+                                                                        InlineTable(
+                                                                            [
+                                                                                Pair {
+                                                                                    key: Identifier(
+                                                                                        "a",
+                                                                                    ),
+                                                                                    value: Float(
+                                                                                        72.0,
+                                                                                    ),
+                                                                                },
+                                                                                Pair {
+                                                                                    key: Identifier(
+                                                                                        "b",
+                                                                                    ),
+                                                                                    value: Integer(
+                                                                                        26,
+                                                                                    ),
+                                                                                },
+                                                                            ],
+                                                                        ),
+                                                                    ),
+                                                                ],
                                                             ),
-                                                        ],
-                                                    ),
+                                                        ),
+                                                    ],
                                                 ),
-                                                Array(
-                                                    Array(
-                                                        [
-                                                            Float(
-                                                                3.14,
+                                            ),
+                                        },
+                                        Pair {
+                                            key: Identifier(
+                                                "temp_targets",
+                                            ),
+                                            value: InlineTable(
+                                                InlineTable(
+                                                    [
+                                                        Pair {
+                                                            key: Identifier(
+                                                                "cpu",
+                                                            ),
+                                                            value: Float(
+                                                                79.5,
+                                                            ),
+                                                        },
+                                                        Pair {
+                                                            key: Identifier(
+                                                                "case",
                                                             ),
-                                                            InlineTable(
+                                                            value: InlineTable(
                                                                 InlineTable(
                                                                     [
                                                                         Pair {
@@ -439,114 +1452,73 @@ role = "backend""#;
                                                                     ],
                                                                 ),
                                                             ),
-                                                        ],
-                                                    ),
+                                                        },
+                                                    ],
                                                 ),
-                                            ],
-                                        ),
-                                    ),
-                                },
-                                Pair {
-                                    key: Identifier(
-                                        "temp_targets",
-                                    ),
-                                    value: InlineTable(
-                                        InlineTable(
-                                            [
-                                                Pair {
-                                                    key: Identifier(
-                                                        "cpu",
-                                                    ),
-                                                    value: Float(
-                                                        79.5,
-                                                    ),
-                                                },
-                                                Pair {
-                                                    key: Identifier(
-                                                        "case",
-                                                    ),
-                                                    value: InlineTable(
-                                                        InlineTable(
-                                                            [
-                                                                Pair {
-                                                                    key: Identifier(
-                                                                        "a",
-                                                                    ),
-                                                                    value: Float(
-                                                                        72.0,
-                                                                    ),
-                                                                },
-                                                                Pair {
-                                                                    key: Identifier(
-                                                                        "b",
-                                                                    ),
-                                                                    value: Integer(
-                                                                        26,
-                                                                    ),
-                                                                },
-                                                            ],
-                                                        ),
-                                                    ),
-                                                },
-                                            ],
-                                        ),
-                                    ),
-                                },
-                            ],
-                        ),
-                    },
-                    Table {
-                        header: Identifier(
-                            "servers-alpha",
-                        ),
-                        body: InlineTable(
-                            [
-                                Pair {
-                                    key: Identifier(
-                                        "ip",
-                                    ),
-                                    value: String(
-                                        "10.0.0.1",
-                                    ),
-                                },
-                                Pair {
-                                    key: Identifier(
-                                        "role",
-                                    ),
-                                    value: String(
-                                        "frontend",
-                                    ),
-                                },
-                            ],
-                        ),
-                    },
-                    Table {
-                        header: Identifier(
-                            "servers-beta",
-                        ),
-                        body: InlineTable(
-                            [
-                                Pair {
-                                    key: Identifier(
-                                        "ip",
-                                    ),
-                                    value: String(
-                                        "10.0.0.2",
-                                    ),
-                                },
-                                Pair {
-                                    key: Identifier(
-                                        "role",
-                                    ),
-                                    value: String(
-                                        "backend",
-                                    ),
-                                },
-                            ],
-                        ),
-                    },
-                ],
+                                            ),
+                                        },
+                                    ],
+                                ),
+                            ),
+                        },
+                        Pair {
+                            key: Identifier(
+                                "servers-alpha",
+                            ),
+                            value: InlineTable(
+                                InlineTable(
+                                    [
+                                        Pair {
+                                            key: Identifier(
+                                                "ip",
+                                            ),
+                                            value: String(
+                                                "10.0.0.1",
+                                            ),
+                                        },
+                                        Pair {
+                                            key: Identifier(
+                                                "role",
+                                            ),
+                                            value: String(
+                                                "frontend",
+                                            ),
+                                        },
+                                    ],
+                                ),
+                            ),
+                        },
+                        Pair {
+                            key: Identifier(
+                                "servers-beta",
+                            ),
+                            value: InlineTable(
+                                InlineTable(
+                                    [
+                                        Pair {
+                                            key: Identifier(
+                                                "ip",
+                                            ),
+                                            value: String(
+                                                "10.0.0.2",
+                                            ),
+                                        },
+                                        Pair {
+                                            key: Identifier(
+                                                "role",
+                                            ),
+                                            value: String(
+                                                "backend",
+                                            ),
+                                        },
+                                    ],
+                                ),
+                            ),
+                        },
+                    ],
+                ),
             ),
+            [],
         )
         "#);
     }
@@ -0,0 +1,264 @@
+use crate::{
+    ir::{Header, Identifier, Value},
+    parser::{Error, ErrorKind},
+};
+use std::collections::{hash_map::Entry, HashMap};
+
+// how a key was defined, which decides whether it can be defined again
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    // any value other than an inline table, arrays included
+    Value,
+    InlineTable,
+    // a table created by a dotted key like `a.b = 1`
+    Dotted,
+    // a table created by a header for one of its children, like `a` in `[a.b]`
+    Implicit,
+    Header,
+    ArrayOfTables,
+}
+
+#[derive(Debug)]
+struct Node<'a> {
+    kind: Kind,
+    // the input the definition starts at
+    at: &'a str,
+    // the keys defined under a table, which are the ones of the last element
+    // in case of an array of tables
+    children: Nodes<'a>,
+}
+
+type Nodes<'a> = HashMap<Identifier, Node<'a>>;
+
+// keeps track of the keys defined so far, since TOML allows defining a key
+// or a table only once
+#[derive(Debug, Default)]
+pub struct Validator<'a> {
+    root: Nodes<'a>,
+    // the keys of the last `[table]` or `[[array]]` header
+    section: Vec<Identifier>,
+}
+
+impl<'a> Node<'a> {
+    fn new(kind: Kind, at: &'a str) -> Self {
+        let children = HashMap::new();
+        Self { kind, at, children }
+    }
+}
+
+// returns the node with the key, adding one if there's none
+fn find_or_insert<'a, 'b>(
+    nodes: &'b mut Nodes<'a>,
+    key: &Identifier,
+    kind: Kind,
+    at: &'a str,
+) -> &'b mut Node<'a> {
+    nodes
+        .entry(key.clone())
+        .or_insert_with(|| Node::new(kind, at))
+}
+
+impl<'a> Validator<'a> {
+    pub fn header(&mut self, at: &'a str, header: &Header) -> Result<(), Error<&'a str>> {
+        let error = |kind| Err(Error::new(at, kind));
+        let (keys, is_array) = match header {
+            Header::Table(keys) => (keys, false),
+            Header::ArrayOfTables(keys) => (keys, true),
+        };
+        let (key, parents) = keys.split_last().expect("table header is never empty");
+
+        let mut nodes = &mut self.root;
+        for key in parents {
+            let node = find_or_insert(nodes, key, Kind::Implicit, at);
+            match node.kind {
+                Kind::Dotted | Kind::Implicit | Kind::Header | Kind::ArrayOfTables => {}
+                Kind::InlineTable => return error(ErrorKind::InlineTableExtended(node.at)),
+                Kind::Value => return error(ErrorKind::DuplicateKey(node.at)),
+            }
+            nodes = &mut node.children;
+        }
+
+        let kind = if is_array {
+            Kind::ArrayOfTables
+        } else {
+            Kind::Header
+        };
+        match nodes.entry(key.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(Node::new(kind, at));
+            }
+            Entry::Occupied(entry) => match entry.into_mut() {
+                // a table created for its children may be defined later on
+                node if node.kind == Kind::Implicit && !is_array => {
+                    node.kind = kind;
+                    node.at = at;
+                }
+                // each `[[array]]` header starts a new element
+                node if node.kind == Kind::ArrayOfTables && is_array => {
+                    node.children.clear();
+                }
+                node => match node.kind {
+                    Kind::Value => return error(ErrorKind::DuplicateKey(node.at)),
+                    Kind::InlineTable => return error(ErrorKind::InlineTableExtended(node.at)),
+                    Kind::Dotted => return error(ErrorKind::DottedKeyClash(node.at)),
+                    Kind::Implicit | Kind::Header | Kind::ArrayOfTables => {
+                        return error(ErrorKind::DuplicateTable(node.at))
+                    }
+                },
+            },
+        }
+
+        self.section = keys.clone();
+        Ok(())
+    }
+
+    // checks a pair of the current section
+    pub fn pair(
+        &mut self,
+        at: &'a str,
+        keys: &[Identifier],
+        value: &Value,
+    ) -> Result<(), Error<&'a str>> {
+        let error = |kind| Err(Error::new(at, kind));
+        let (key, parents) = keys.split_last().expect("key is never empty");
+
+        let mut nodes = &mut self.root;
+        for key in &self.section {
+            nodes = &mut find_or_insert(nodes, key, Kind::Implicit, at).children;
+        }
+        for key in parents {
+            let node = find_or_insert(nodes, key, Kind::Dotted, at);
+            match node.kind {
+                Kind::Dotted => {}
+                Kind::Value => return error(ErrorKind::DuplicateKey(node.at)),
+                Kind::InlineTable => return error(ErrorKind::InlineTableExtended(node.at)),
+                Kind::Implicit | Kind::Header | Kind::ArrayOfTables => {
+                    return error(ErrorKind::DottedKeyClash(node.at))
+                }
+            }
+            nodes = &mut node.children;
+        }
+
+        if let Some(node) = nodes.get(key) {
+            return error(ErrorKind::DuplicateKey(node.at));
+        }
+        let kind = match value {
+            Value::InlineTable(_) => Kind::InlineTable,
+            _ => Kind::Value,
+        };
+        nodes.insert(key.clone(), Node::new(kind, at));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::parse;
+    use insta::{assert_compact_debug_snapshot, assert_snapshot};
+    use std::time::Instant;
+
+    #[test]
+    fn test_validate_valid() {
+        let s = r#"
+a.b = 1
+a.c.d = 2
+[x.y.z]
+[x]
+e = { f = 1 }
+[[x.arr]]
+[x.arr.t]
+[[x.arr]]
+[x.arr.t]
+[a.g]
+h = 1
+"#;
+        let r = parse(s).map(|_| ());
+        assert_compact_debug_snapshot!(r, @"Ok(())")
+    }
+
+    #[test]
+    fn test_validate_duplicate_key() {
+        let r = [
+            "a = 1\na = 2",
+            "a = 1\n[a]\n",
+            "a.b = 1\na.b.c = 2",
+            "a = []\n[[a]]\n",
+        ]
+        .map(|s| parse(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[[Diagnostic { kind: Semantic, path: None, message: "duplicate key", line: 2, column: 1, span: 6..11, snippet: "a = 2", width: 5, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..5, snippet: "a = 1", width: 5, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate key", line: 2, column: 1, span: 6..9, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..5, snippet: "a = 1", width: 5, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate key", line: 2, column: 1, span: 8..17, snippet: "a.b.c = 2", width: 9, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..7, snippet: "a.b = 1", width: 7, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate key", line: 2, column: 1, span: 7..12, snippet: "[[a]]", width: 5, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..6, snippet: "a = []", width: 6, note: None }) }]]"#)
+    }
+
+    #[test]
+    fn test_validate_duplicate_table() {
+        let r = [
+            "[a]\n[a]\n",
+            "[a.b]\n[a]\n[a]\n",
+            "[[a]]\n[a]\n",
+            "[a]\n[[a]]\n",
+        ]
+        .map(|s| parse(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[[Diagnostic { kind: Semantic, path: None, message: "duplicate table", line: 2, column: 1, span: 4..7, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..3, snippet: "[a]", width: 3, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate table", line: 3, column: 1, span: 10..13, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 2, column: 1, span: 6..9, snippet: "[a]", width: 3, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate table", line: 2, column: 1, span: 6..9, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..5, snippet: "[[a]]", width: 5, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate table", line: 2, column: 1, span: 4..9, snippet: "[[a]]", width: 5, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..3, snippet: "[a]", width: 3, note: None }) }]]"#)
+    }
+
+    #[test]
+    fn test_validate_inline_table_extended() {
+        let r = [
+            "a = {}\n[a]\n",
+            "a = {}\n[a.b]\n",
+            "a = { b = 1 }\na.c = 2",
+            "a = { b = 1, b = 2 }",
+        ]
+        .map(|s| parse(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[[Diagnostic { kind: Semantic, path: None, message: "inline tables can't be extended", line: 2, column: 1, span: 7..10, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..6, snippet: "a = {}", width: 6, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "inline tables can't be extended", line: 2, column: 1, span: 7..12, snippet: "[a.b]", width: 5, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..6, snippet: "a = {}", width: 6, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "inline tables can't be extended", line: 2, column: 1, span: 14..21, snippet: "a.c = 2", width: 7, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..13, snippet: "a = { b = 1 }", width: 13, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "duplicate key", line: 1, column: 14, span: 13..18, snippet: "a = { b = 1, b = 2 }", width: 5, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 7, span: 6..11, snippet: "a = { b = 1, b = 2 }", width: 5, note: None }) }]]"#)
+    }
+
+    #[test]
+    fn test_validate_dotted_key_clash() {
+        let r = [
+            "a.b = 1\n[a]\n",
+            "[a.b.c]\n[a]\nb.c.d = 1",
+            "[[a.b]]\n[a]\nb.c = 1",
+        ]
+        .map(|s| parse(s).unwrap_err());
+        assert_compact_debug_snapshot!(r, @r#"[[Diagnostic { kind: Semantic, path: None, message: "dotted keys and headers can't define the same table", line: 2, column: 1, span: 8..11, snippet: "[a]", width: 3, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..7, snippet: "a.b = 1", width: 7, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "dotted keys and headers can't define the same table", line: 3, column: 1, span: 12..21, snippet: "b.c.d = 1", width: 9, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..7, snippet: "[a.b.c]", width: 7, note: None }) }], [Diagnostic { kind: Semantic, path: None, message: "dotted keys and headers can't define the same table", line: 3, column: 1, span: 12..19, snippet: "b.c = 1", width: 7, note: Some(Diagnostic { kind: Semantic, path: None, message: "first defined here", line: 1, column: 1, span: 0..7, snippet: "[[a.b]]", width: 7, note: None }) }]]"#)
+    }
+
+    #[test]
+    fn test_validate_scaling() {
+        // the fastest of a few runs, to leave out the noise of other tests
+        let time = |n| {
+            let s: String = (0..n).map(|i| format!("k{i} = 1\n[t{i}]\n")).collect();
+            let run = || {
+                let start = Instant::now();
+                parse(&s).unwrap();
+                start.elapsed()
+            };
+            (0..3).map(|_| run()).min().unwrap().as_secs_f64()
+        };
+        // four times the keys would take sixteen times as long if each key
+        // was looked up among all the others
+        let ratio = time(20_000) / time(5_000);
+        assert!(ratio < 8.0, "parsing 4x the keys took {ratio:.1}x as long");
+    }
+
+    #[test]
+    fn test_validate_display() {
+        let r = parse("a = 1\nb = 2\na = 3")
+            .unwrap_err()
+            .remove(0)
+            .to_string();
+        assert_snapshot!(r, @r"
+        error: duplicate key
+         --> 3:1
+          |
+        3 | a = 3
+          | ^^^^^
+
+        note: first defined here
+         --> 1:1
+          |
+        1 | a = 1
+          | -----
+        ")
+    }
+}
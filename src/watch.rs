@@ -0,0 +1,73 @@
+use crate::batch::Source;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+// how often the inputs are checked for changes. Polling works the same on
+// every platform and for any number of files, and editors that save by
+// renaming a new file over the old one don't need special care.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// the modification times of the files seen in the last poll, which is none
+// for files that can't be read
+#[derive(Debug, Default)]
+pub struct Stamps(HashMap<PathBuf, Option<SystemTime>>);
+
+impl Stamps {
+    // keeps the files that are new or changed since the last call
+    pub fn retain_changed(&mut self, sources: &mut Vec<Source>) {
+        let stamps: HashMap<_, _> = sources
+            .iter()
+            .map(|source| {
+                let modified = fs::metadata(&source.path).and_then(|m| m.modified());
+                (source.path.clone(), modified.ok())
+            })
+            .collect();
+        sources.retain(|source| self.0.get(&source.path) != Some(&stamps[&source.path]));
+        self.0 = stamps;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::TempDir;
+    use insta::assert_compact_debug_snapshot;
+    use std::fs::File;
+
+    #[test]
+    fn test_retain_changed() {
+        let dir = TempDir::new("watch");
+        let [a, b] = ["a.toml", "b.toml"].map(|name| {
+            let path = dir.join(name);
+            fs::write(&path, "").unwrap();
+            Source {
+                path,
+                base: dir.to_path_buf(),
+            }
+        });
+
+        let mut stamps = Stamps::default();
+        let mut r = Vec::new();
+        let poll = |stamps: &mut Stamps| {
+            let mut sources = vec![a.clone(), b.clone()];
+            stamps.retain_changed(&mut sources);
+            let names = sources.iter().map(|s| s.path.strip_prefix(&dir).unwrap());
+            names
+                .map(|name| name.display().to_string())
+                .collect::<Vec<_>>()
+        };
+        r.push(poll(&mut stamps));
+        r.push(poll(&mut stamps));
+        let file = File::options().write(true).open(&b.path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        r.push(poll(&mut stamps));
+        fs::remove_file(&a.path).unwrap();
+        r.push(poll(&mut stamps));
+        r.push(poll(&mut stamps));
+        assert_compact_debug_snapshot!(r, @r#"[["a.toml", "b.toml"], [], ["b.toml"], ["a.toml"], []]"#)
+    }
+}